@@ -0,0 +1,42 @@
+use crate::{utils::Scalar, Operation, Variable, VariableData};
+
+/// User-defined scalar operation, e.g. a fused activation.
+///
+/// Implement it outside of the crate and apply it with `Variable::custom`.
+pub trait CustomOp<T: Scalar = f64> {
+    /// Name stored in `Operation::Custom` of the resulting node.
+    fn name(&self) -> &str;
+
+    /// Computes the output value from the values of the inputs.
+    fn forward(&self, inputs: &[T]) -> T;
+
+    /// Returns `grad * d(output)/d(input_i)` for every input.
+    fn backward(&self, inputs: &[T], output: T, grad: T) -> Vec<T>;
+}
+
+impl<T: Scalar> Variable<T> {
+    /// Applies `op` to `inputs`, recording a single node in the graph.
+    pub fn custom<O: CustomOp<T> + 'static>(op: O, inputs: &[Variable<T>]) -> Variable<T> {
+        let values = inputs.iter().map(|x| x.data()).collect::<Vec<_>>();
+        let name = String::from(op.name());
+        Variable::from_op(
+            op.forward(&values),
+            Operation::Custom(name),
+            || inputs.to_vec(),
+            move |x: &VariableData<T>| {
+                let values = x.children.iter().map(|c| c.data()).collect::<Vec<_>>();
+                let grads = op.backward(&values, x.data, x.grad);
+                assert_eq!(
+                    grads.len(),
+                    x.children.len(),
+                    "custom op `{}` must return one grad per input",
+                    op.name()
+                );
+                x.children
+                    .iter()
+                    .zip(grads)
+                    .for_each(|(child, g)| child.borrow_mut().grad += g);
+            },
+        )
+    }
+}
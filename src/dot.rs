@@ -0,0 +1,77 @@
+use crate::{utils::Scalar, Operation, Variable};
+use std::{collections::HashSet, fmt::Write};
+
+impl<T: Scalar> Variable<T> {
+    /// Renders the graph under `self` in Graphviz DOT format.
+    ///
+    /// One node per `VariableData`, labelled with op, data and grad
+    /// (only the op for the hub of a matrix product).
+    /// Edges go from operands to the result.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(false)
+    }
+
+    /// Same as `to_dot`, but if `collapse_sums` is set, the fan-in of every
+    /// `Operation::Sum` node is drawn as a single box instead of one node per element.
+    pub fn to_dot_with(&self, collapse_sums: bool) -> String {
+        let mut out = String::from("digraph {\n    rankdir=LR;\n");
+        let mut used = HashSet::new();
+        let mut stack = vec![self.clone()];
+        while let Some(node) = stack.pop() {
+            let node = node.borrow();
+            if !used.insert(node.id) {
+                continue;
+            }
+            let op = match &node.op {
+                Some(Operation::None) | None => String::from("leaf"),
+                Some(op) => format!("{:?}", op),
+            };
+            let label = match node.op {
+                // the hub of a matrix product has no value of its own
+                Some(Operation::MatMulHub { .. }) => escape(&op),
+                _ => format!(
+                    "{} | data {:.4} | grad {:.4}",
+                    escape(&op),
+                    node.data,
+                    node.grad
+                ),
+            };
+            writeln!(
+                out,
+                "    n{} [shape=record, label=\"{{ {} }}\"];",
+                node.id, label
+            )
+            .unwrap();
+
+            if collapse_sums && node.op == Some(Operation::Sum) {
+                writeln!(
+                    out,
+                    "    n{}_in [shape=box, style=dashed, label=\"{} inputs\"];\n    n{}_in -> n{};",
+                    node.id,
+                    node.children.len(),
+                    node.id,
+                    node.id
+                )
+                .unwrap();
+                continue;
+            }
+            node.children.iter().for_each(|child| {
+                writeln!(out, "    n{} -> n{};", child.borrow().id, node.id).unwrap();
+                stack.push(child.clone());
+            });
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Escapes characters that have a meaning inside of a record label.
+fn escape(label: &str) -> String {
+    label.chars().fold(String::new(), |mut acc, c| {
+        if "{}|<>\"\\".contains(c) {
+            acc.push('\\');
+        }
+        acc.push(c);
+        acc
+    })
+}
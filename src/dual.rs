@@ -0,0 +1,115 @@
+use crate::utils::max;
+use auto_ops::*;
+
+/// Dual number `data + tangent * e`, `e^2 = 0`. Forward-mode counterpart of `Variable`.
+///
+/// Every op carries the directional derivative along with the value, so one
+/// forward pass gives a Jacobian-vector product.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Dual {
+    pub data: f64,
+    pub tangent: f64,
+}
+
+impl_op_ex!(+|a: &Dual, b: &Dual| -> Dual { Dual::new(a.data + b.data, a.tangent + b.tangent) });
+impl_op_ex_commutative!(+|a: &Dual, b: f64| -> Dual { Dual::new(a.data + b, a.tangent) });
+
+impl_op_ex!(*|a: &Dual, b: &Dual| -> Dual {
+    Dual::new(a.data * b.data, a.tangent * b.data + a.data * b.tangent)
+});
+impl_op_ex_commutative!(*|a: &Dual, b: f64| -> Dual { Dual::new(a.data * b, a.tangent * b) });
+
+impl_op_ex!(/|a: &Dual, b: &Dual| -> Dual {
+    assert!(b.data != 0.0, "dividing by zero");
+    Dual::new(
+        a.data / b.data,
+        (a.tangent * b.data - a.data * b.tangent) / (b.data * b.data),
+    )
+});
+impl_op_ex!(/|a: &Dual, b: f64| -> Dual { a / Dual::from(b) });
+impl_op_ex!(/|a: f64, b: &Dual| -> Dual { Dual::from(a) / b });
+
+impl_op!(-|a: &Dual| -> Dual { Dual::new(-a.data, -a.tangent) });
+impl_op!(-|a: Dual| -> Dual { -&a });
+
+impl_op_ex!(-|a: &Dual, b: &Dual| -> Dual { a + -b });
+impl_op_ex!(-|a: &Dual, b: f64| -> Dual { a + -b });
+impl_op_ex!(-|a: f64, b: &Dual| -> Dual { a + -b });
+
+impl Dual {
+    pub fn new(data: f64, tangent: f64) -> Dual {
+        Dual { data, tangent }
+    }
+
+    /// Constant: zero tangent.
+    pub fn from<T: Into<f64>>(f: T) -> Dual {
+        Dual::new(f.into(), 0.0)
+    }
+
+    /// Input we differentiate by: unit tangent.
+    pub fn variable<T: Into<f64>>(f: T) -> Dual {
+        Dual::new(f.into(), 1.0)
+    }
+
+    pub fn data(&self) -> f64 {
+        self.data
+    }
+
+    pub fn tangent(&self) -> f64 {
+        self.tangent
+    }
+
+    pub fn pow(&self, p: f64) -> Dual {
+        Dual::new(
+            self.data.powf(p),
+            self.tangent * p * self.data.powf(p - 1.0),
+        )
+    }
+
+    pub fn sin(&self) -> Dual {
+        Dual::new(self.data.sin(), self.tangent * self.data.cos())
+    }
+
+    pub fn cos(&self) -> Dual {
+        Dual::new(self.data.cos(), -self.tangent * self.data.sin())
+    }
+
+    pub fn relu(&self) -> Dual {
+        let tangent = if self.data > 0.0 { self.tangent } else { 0.0 };
+        Dual::new(max(self.data, 0.0), tangent)
+    }
+
+    pub fn exp(&self) -> Dual {
+        let exp = self.data.exp();
+        Dual::new(exp, self.tangent * exp)
+    }
+
+    pub fn ln(&self) -> Dual {
+        Dual::new(self.data.ln(), self.tangent / self.data)
+    }
+
+    pub fn tan(&self) -> Dual {
+        Dual::new(self.data.tan(), self.tangent / self.data.cos().powf(2.0))
+    }
+
+    pub fn silu(&self) -> Dual {
+        self * self.sigmoid()
+    }
+
+    pub fn sigmoid(&self) -> Dual {
+        (1.0 + (-self).exp()).pow(-1.0)
+    }
+}
+
+/// Jacobian-vector product in a single forward pass.
+///
+/// Returns `(f(x), J(x) * v)`.
+pub fn jvp(f: impl Fn(&[Dual]) -> Vec<Dual>, x: &[f64], v: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    assert_eq!(x.len(), v.len(), "x and v must have the same length");
+    let inputs = x
+        .iter()
+        .zip(v.iter())
+        .map(|(&x, &v)| Dual::new(x, v))
+        .collect::<Vec<_>>();
+    f(&inputs).iter().map(|y| (y.data, y.tangent)).unzip()
+}
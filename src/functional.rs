@@ -0,0 +1,45 @@
+use crate::{utils::Scalar, Tensor1D, Tensor2D, Variable};
+
+/// Fresh leaves holding the values of `x`, so grads of `x` itself stay untouched.
+fn leaves<T: Scalar>(x: &Tensor1D<T>) -> Tensor1D<T> {
+    Tensor1D::from_values(&x.borrow().iter().map(|v| v.data()).collect::<Vec<_>>())
+}
+
+/// Grads of every leaf w.r.t. `output`, one reverse pass.
+fn grad_row<T: Scalar>(output: &Variable<T>, inputs: &Tensor1D<T>) -> Vec<T> {
+    inputs.borrow().iter().for_each(|x| x.zero_grad());
+    output.clone().backward();
+    inputs.borrow().iter().map(|x| x.grad()).collect()
+}
+
+/// Jacobian of `f` at `x`: `J[i][j] = d f(x)_i / d x_j`.
+///
+/// Runs one reverse pass per output.
+pub fn jacobian<T: Scalar>(
+    f: impl Fn(&Tensor1D<T>) -> Tensor1D<T>,
+    x: &Tensor1D<T>,
+) -> Tensor2D<T> {
+    let inputs = leaves(x);
+    let outputs = f(&inputs);
+    let rows = outputs
+        .borrow()
+        .iter()
+        .map(|y| grad_row(y, &inputs))
+        .collect::<Vec<_>>();
+    Tensor2D::from_values(&rows)
+}
+
+/// Hessian of `f` at `x`: `H[i][j] = d^2 f(x) / dx_i dx_j`.
+///
+/// The gradient is built with `Variable::backward_create_graph`, then every
+/// entry of it is backpropagated once more (reverse-over-reverse).
+pub fn hessian<T: Scalar>(f: impl Fn(&Tensor1D<T>) -> Variable<T>, x: &Tensor1D<T>) -> Tensor2D<T> {
+    let inputs = leaves(x);
+    let output = f(&inputs);
+    let grads = output.backward_create_graph(&inputs.borrow());
+    let rows = grads
+        .iter()
+        .map(|g| grad_row(g, &inputs))
+        .collect::<Vec<_>>();
+    Tensor2D::from_values(&rows)
+}
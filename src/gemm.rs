@@ -0,0 +1,71 @@
+use crate::utils::Scalar;
+
+/// Rows of `a` (and of the output) handled by one task.
+const MC: usize = 64;
+/// Depth of a block: `KC` rows of `b` are reused for all `MC` rows of `a`.
+const KC: usize = 256;
+/// Width of a block of `b` and of the output row segments.
+const NC: usize = 512;
+/// Products with fewer multiply-adds aren't worth sending to other threads.
+#[cfg(feature = "rayon")]
+const PARALLEL_MIN: usize = 1 << 18;
+
+/// `a` is `n x k`, `b` is `k x m`, both row-major.
+pub(crate) fn matmul<T: Scalar>(a: &[T], b: &[T], n: usize, k: usize, m: usize) -> Vec<T> {
+    assert_eq!(a.len(), n * k);
+    assert_eq!(b.len(), k * m);
+    let mut out = vec![T::zero(); n * m];
+    if m == 0 {
+        return out;
+    }
+
+    #[cfg(feature = "rayon")]
+    if n * k * m >= PARALLEL_MIN {
+        use rayon::prelude::*;
+        out.par_chunks_mut(MC * m)
+            .enumerate()
+            .for_each(|(i, c)| block(&a[i * MC * k..], b, c, k, m));
+        return out;
+    }
+
+    out.chunks_mut(MC * m)
+        .enumerate()
+        .for_each(|(i, c)| block(&a[i * MC * k..], b, c, k, m));
+    out
+}
+
+/// Adds `a * b` to the rows of `c`, where `a` holds (at least) as many rows as `c`.
+///
+/// The loops are tiled so that a `KC x NC` panel of `b` stays in cache while it is
+/// used by every row of `c`; the innermost loop is a contiguous `c += x * b` that
+/// the compiler vectorizes.
+fn block<T: Scalar>(a: &[T], b: &[T], c: &mut [T], k: usize, m: usize) {
+    let rows = c.len() / m;
+    for j0 in (0..m).step_by(NC) {
+        let j1 = (j0 + NC).min(m);
+        for p0 in (0..k).step_by(KC) {
+            let p1 = (p0 + KC).min(k);
+            for i in 0..rows {
+                let c_row = &mut c[i * m + j0..i * m + j1];
+                for p in p0..p1 {
+                    let x = a[i * k + p];
+                    c_row
+                        .iter_mut()
+                        .zip(&b[p * m + j0..p * m + j1])
+                        .for_each(|(o, &y)| *o += x * y);
+                }
+            }
+        }
+    }
+}
+
+/// `a` is `r x c`, returns `c x r`.
+pub(crate) fn transpose<T: Scalar>(a: &[T], r: usize, c: usize) -> Vec<T> {
+    let mut out = vec![T::zero(); r * c];
+    for i in 0..r {
+        for j in 0..c {
+            out[j * r + i] = a[i * c + j];
+        }
+    }
+    out
+}
@@ -0,0 +1,155 @@
+use crate::{utils::Scalar, Operation, Tensor1D, Variable};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+impl Operation {
+    /// Inputs that `grad_graph` differentiates w.r.t., the children of the node.
+    ///
+    /// The exception is an entry of a fused matrix product: it is taken as the dot product
+    /// of a row of `A` and a column of `B`, whose entries are the children of the hub.
+    fn graph_children<T: Scalar>(&self, children: &[Variable<T>]) -> Vec<Variable<T>> {
+        let &Operation::MatMul { row, col } = self else {
+            return children.to_vec();
+        };
+        let hub = children[0].borrow();
+        let Some(Operation::MatMulHub { n, k, m }) = hub.op else {
+            panic!("entry of a matrix product must have the hub as its child");
+        };
+        let (a, b) = hub.children.split_at(n * k);
+        a[row * k..(row + 1) * k]
+            .iter()
+            .cloned()
+            .chain((0..k).map(|l| b[l * m + col].clone()))
+            .collect()
+    }
+
+    /// Grads of the children of `out`, built from regular ops so they can be differentiated again.
+    fn grad_graph<T: Scalar>(
+        &self,
+        children: &[Variable<T>],
+        out: &Variable<T>,
+        grad: &Variable<T>,
+    ) -> Vec<Variable<T>> {
+        let a = &children[0];
+        match self {
+            Operation::ADD => vec![grad.clone(), grad.clone()],
+            Operation::MUL => vec![grad * &children[1], grad * a],
+            Operation::DIV => {
+                let b = &children[1];
+                vec![grad / b, -(grad * a) / b.pow(T::from_f64(2.0))]
+            }
+            Operation::Pow(p) => vec![grad * a.pow(T::from_f64(p - 1.0)) * T::from_f64(*p)],
+            Operation::Sin => vec![grad * a.cos()],
+            Operation::Cos => vec![-(grad * a.sin())],
+            Operation::Exp => vec![grad * out],
+            Operation::Ln => vec![grad / a],
+            Operation::Tan => vec![grad / a.cos().pow(T::from_f64(2.0))],
+            Operation::Relu => {
+                if a.data() > T::zero() {
+                    vec![grad.clone()]
+                } else {
+                    vec![Variable::from_value(T::zero())]
+                }
+            }
+            Operation::Sum => children.iter().map(|_| grad.clone()).collect(),
+            Operation::Mean => {
+                let g = grad / T::from_f64(children.len() as f64);
+                children.iter().map(|_| g.clone()).collect()
+            }
+            Operation::Max | Operation::Min => {
+                let arg = children.iter().position(|c| c.data() == out.data());
+                (0..children.len())
+                    .map(|i| {
+                        if arg == Some(i) {
+                            grad.clone()
+                        } else {
+                            Variable::from_value(T::zero())
+                        }
+                    })
+                    .collect()
+            }
+            // equal children, see `Tensor1D::std`
+            Operation::Std if out.data() == T::zero() => children
+                .iter()
+                .map(|_| Variable::from_value(T::zero()))
+                .collect(),
+            Operation::Var | Operation::Std => {
+                let n = children.len();
+                let x = Tensor1D(Rc::new(RefCell::new(children.to_vec())), n);
+                let mean = x.mean();
+                // d var / dx_i = 2 (x_i - mean) / n, d std / dx_i = (x_i - mean) / (n std)
+                let scale = match self {
+                    Operation::Var => grad * T::from_f64(2.0 / n as f64),
+                    _ => grad / (out * T::from_f64(n as f64)),
+                };
+                children.iter().map(|c| (c - &mean) * &scale).collect()
+            }
+            Operation::LogSumExp => children.iter().map(|c| (c - out).exp() * grad).collect(),
+            Operation::Softmax => {
+                let g = grad * out;
+                vec![g.clone(), -g]
+            }
+            Operation::LogSoftmax => vec![grad.clone(), -grad],
+            Operation::Dot | Operation::MatMul { .. } => {
+                let (x, y) = children.split_at(children.len() / 2);
+                y.iter().chain(x).map(|c| grad * c).collect()
+            }
+            Operation::Custom(name) => {
+                panic!("custom op `{}` doesn't support create_graph", name)
+            }
+            Operation::MatMulHub { .. } | Operation::MatMulGrad { .. } => {
+                unreachable!("grads of a matrix product skip the hub, see `graph_children`")
+            }
+            Operation::None => panic!("node with a backward fn must have an op"),
+        }
+    }
+}
+
+impl<T: Scalar> Variable<T> {
+    /// `create_graph` mode of backward.
+    ///
+    /// Returns d(self)/d(input) for every input as a `Variable` built from the existing ops,
+    /// so the result can be backpropagated through again (Hessian-vector products,
+    /// gradient penalties, ...). The `grad` fields of the graph are left untouched.
+    pub fn backward_create_graph(&self, inputs: &[Variable<T>]) -> Vec<Variable<T>> {
+        let mut grads: HashMap<usize, Variable<T>> = HashMap::new();
+        grads.insert(self.borrow().id, Variable::from_value(T::one()));
+
+        self.topological_order().iter().rev().for_each(|it| {
+            let node = it.borrow();
+            let (Some(grad), Some(op)) = (grads.get(&node.id).cloned(), &node.op) else {
+                return;
+            };
+            if node.fun.is_none() {
+                // leaf or detached node
+                return;
+            }
+            let children = op.graph_children(&node.children);
+            let child_grads = op.grad_graph(&children, it, &grad);
+            children.iter().zip(child_grads).for_each(|(child, g)| {
+                let id = child.borrow().id;
+                let sum = match grads.remove(&id) {
+                    Some(prev) => prev + g,
+                    None => g,
+                };
+                grads.insert(id, sum);
+            });
+        });
+
+        inputs
+            .iter()
+            .map(|x| {
+                grads
+                    .get(&x.borrow().id)
+                    .cloned()
+                    .unwrap_or_else(|| Variable::from_value(T::zero()))
+            })
+            .collect()
+    }
+}
+
+/// d(output)/d(input) for every input, see `Variable::backward_create_graph`.
+///
+/// `grad(&grad(&f, &[x.clone()])[0], &[x])` is the second derivative of `f`.
+pub fn grad<T: Scalar>(output: &Variable<T>, inputs: &[Variable<T>]) -> Vec<Variable<T>> {
+    output.backward_create_graph(inputs)
+}
@@ -0,0 +1,254 @@
+use crate::{no_grad, utils::Scalar, Operation, Tensor, Tensor1D, Tensor2D, Variable};
+use std::{collections::HashSet, fmt};
+
+/// Single number that `gradcheck` perturbs: a leaf `Variable` or an element of a `Tensor`.
+#[derive(Clone)]
+pub enum Leaf<T: Scalar = f64> {
+    Variable(Variable<T>),
+    /// Element of the tensor at the given index of its data.
+    Element(Tensor<T>, usize),
+}
+
+impl<T: Scalar> Leaf<T> {
+    fn data(&self) -> T {
+        match self {
+            Leaf::Variable(x) => x.data(),
+            Leaf::Element(x, i) => x.borrow().data[*i],
+        }
+    }
+
+    fn set_data(&self, value: T) {
+        match self {
+            Leaf::Variable(x) => x.borrow_mut().data = value,
+            Leaf::Element(x, i) => x.borrow_mut().data[*i] = value,
+        }
+    }
+
+    fn grad(&self) -> T {
+        match self {
+            Leaf::Variable(x) => x.grad(),
+            Leaf::Element(x, i) => x.borrow().grad[*i],
+        }
+    }
+
+    fn zero_grad(&self) {
+        match self {
+            Leaf::Variable(x) => x.borrow_mut().grad = T::zero(),
+            Leaf::Element(x, i) => x.borrow_mut().grad[*i] = T::zero(),
+        }
+    }
+}
+
+/// Anything built from leaf `Variable`s or `Tensor`s that `gradcheck` can perturb.
+pub trait Leaves<T: Scalar = f64> {
+    fn leaves(&self) -> Vec<Leaf<T>>;
+}
+
+impl<T: Scalar> Leaves<T> for Variable<T> {
+    fn leaves(&self) -> Vec<Leaf<T>> {
+        vec![Leaf::Variable(self.clone())]
+    }
+}
+
+impl<T: Scalar> Leaves<T> for Tensor1D<T> {
+    fn leaves(&self) -> Vec<Leaf<T>> {
+        self.borrow().iter().cloned().map(Leaf::Variable).collect()
+    }
+}
+
+impl<T: Scalar> Leaves<T> for Tensor2D<T> {
+    fn leaves(&self) -> Vec<Leaf<T>> {
+        self.borrow()
+            .iter()
+            .flatten()
+            .cloned()
+            .map(Leaf::Variable)
+            .collect()
+    }
+}
+
+impl<T: Scalar> Leaves<T> for Tensor<T> {
+    fn leaves(&self) -> Vec<Leaf<T>> {
+        (0..self.len())
+            .map(|i| Leaf::Element(self.clone(), i))
+            .collect()
+    }
+}
+
+impl<T: Scalar, L: Leaves<T>> Leaves<T> for [L] {
+    fn leaves(&self) -> Vec<Leaf<T>> {
+        self.iter().flat_map(|x| x.leaves()).collect()
+    }
+}
+
+impl<T: Scalar, A: Leaves<T>, B: Leaves<T>> Leaves<T> for (A, B) {
+    fn leaves(&self) -> Vec<Leaf<T>> {
+        let mut out = self.0.leaves();
+        out.extend(self.1.leaves());
+        out
+    }
+}
+
+impl<T: Scalar, A: Leaves<T>, B: Leaves<T>, C: Leaves<T>> Leaves<T> for (A, B, C) {
+    fn leaves(&self) -> Vec<Leaf<T>> {
+        let mut out = (&self.0, &self.1).leaves();
+        out.extend(self.2.leaves());
+        out
+    }
+}
+
+impl<T: Scalar, L: Leaves<T> + ?Sized> Leaves<T> for &L {
+    fn leaves(&self) -> Vec<Leaf<T>> {
+        (*self).leaves()
+    }
+}
+
+/// Mismatch found by `gradcheck`.
+#[derive(Debug, Clone)]
+pub struct GradCheckError<T: Scalar = f64> {
+    /// Index of the leaf, in the order given by `Leaves::leaves`.
+    pub input: usize,
+    pub analytic: T,
+    pub numeric: T,
+    /// First op (counting from the inputs) whose backward disagrees with
+    /// central differences of its own forward, if it could be found.
+    pub op: Option<Operation>,
+}
+
+impl<T: Scalar> fmt::Display for GradCheckError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "grad of input {} is {}, but central differences give {}",
+            self.input, self.analytic, self.numeric
+        )?;
+        match &self.op {
+            Some(op) => write!(f, " (backward of {:?} is wrong)", op),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: Scalar> std::error::Error for GradCheckError<T> {}
+
+/// Single-number result of the function checked by `gradcheck`, e.g. a loss.
+pub trait Output<T: Scalar = f64> {
+    fn value(&self) -> T;
+
+    /// Backpropagates into the grads of the leaves.
+    fn backward(&mut self);
+
+    /// First op whose backward disagrees with its own forward, if it can be found.
+    fn wrong_op(&self, _eps: T, _tol: T) -> Option<Operation> {
+        None
+    }
+}
+
+impl<T: Scalar> Output<T> for Variable<T> {
+    fn value(&self) -> T {
+        self.data()
+    }
+
+    fn backward(&mut self) {
+        Variable::backward(self)
+    }
+
+    fn wrong_op(&self, eps: T, tol: T) -> Option<Operation> {
+        find_wrong_op(self, eps, tol)
+    }
+}
+
+impl<T: Scalar> Output<T> for Tensor<T> {
+    fn value(&self) -> T {
+        self.item()
+    }
+
+    fn backward(&mut self) {
+        Tensor::backward(self)
+    }
+}
+
+fn close<T: Scalar>(analytic: T, numeric: T, tol: T) -> bool {
+    (analytic - numeric).abs() <= tol * (T::one() + numeric.abs())
+}
+
+/// Compares grads from backward with central differences
+/// `(f(x + eps) - f(x - eps)) / 2eps` for every leaf of `inputs`.
+///
+/// Works on `Variable`s and tensors of them as well as on `Tensor`s,
+/// whose every element is a leaf of its own.
+/// Values are compared with a mixed tolerance: `|a - n| <= tol * (1 + |n|)`.
+/// Grads of the graph are overwritten.
+///
+/// Runs in the precision of the inputs; with `f32` a larger `eps` and `tol` are needed.
+pub fn gradcheck<T: Scalar, I: Leaves<T> + ?Sized, O: Output<T>>(
+    f: impl Fn(&I) -> O,
+    inputs: &I,
+    eps: T,
+    tol: T,
+) -> Result<(), GradCheckError<T>> {
+    let leaves = inputs.leaves();
+    let mut out = f(inputs);
+    leaves.iter().for_each(|x| x.zero_grad());
+    out.backward();
+    let analytic = leaves.iter().map(|x| x.grad()).collect::<Vec<_>>();
+
+    for (input, leaf) in leaves.iter().enumerate() {
+        let x = leaf.data();
+        leaf.set_data(x + eps);
+        let plus = no_grad(|| f(inputs).value());
+        leaf.set_data(x - eps);
+        let minus = no_grad(|| f(inputs).value());
+        leaf.set_data(x);
+
+        let numeric = (plus - minus) / (T::from_f64(2.0) * eps);
+        if !close(analytic[input], numeric, tol) {
+            return Err(GradCheckError {
+                input,
+                analytic: analytic[input],
+                numeric,
+                op: out.wrong_op(eps, tol),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks the backward of every node against its own forward, see `Operation::eval`.
+fn find_wrong_op<T: Scalar>(out: &Variable<T>, eps: T, tol: T) -> Option<Operation> {
+    out.topological_order().into_iter().find_map(|it| {
+        it.borrow_mut().grad = T::one();
+        let node = it.borrow();
+        let (Some(fun), Some(op)) = (&node.fun, &node.op) else {
+            return None;
+        };
+        let values = node.children.iter().map(|c| c.data()).collect::<Vec<_>>();
+        op.eval(&values)?;
+
+        node.children
+            .iter()
+            .for_each(|c| c.borrow_mut().grad = T::zero());
+        fun(&node);
+
+        // the same Variable can be several children at once, e.g. in x * x
+        let mut used = HashSet::new();
+        let wrong = node.children.iter().any(|child| {
+            let id = child.borrow().id;
+            if !used.insert(id) {
+                return false;
+            }
+            let shifted = |delta: T| {
+                let v = node
+                    .children
+                    .iter()
+                    .zip(values.iter())
+                    .map(|(c, &v)| if c.borrow().id == id { v + delta } else { v })
+                    .collect::<Vec<_>>();
+                op.eval(&v).unwrap()
+            };
+            let numeric = (shifted(eps) - shifted(-eps)) / (T::from_f64(2.0) * eps);
+            !close(child.grad(), numeric, tol)
+        });
+        wrong.then(|| op.clone())
+    })
+}
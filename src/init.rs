@@ -0,0 +1,128 @@
+use crate::{utils::Scalar, Tensor1D, Tensor2D};
+use rand::Rng;
+use std::f64::consts::PI;
+
+/// Uniform sample from `[low, high)`.
+fn uniform<R: Rng + ?Sized>(rng: &mut R, low: f64, high: f64) -> f64 {
+    low + (high - low) * rng.gen::<f64>()
+}
+
+/// Normal sample with mean 0, by the Box-Muller transform.
+fn normal<R: Rng + ?Sized>(rng: &mut R, std: f64) -> f64 {
+    // `1 - u` is in (0, 1], so `ln` stays finite
+    let (u, v) = (1.0 - rng.gen::<f64>(), rng.gen::<f64>());
+    std * (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+impl<T: Scalar> Tensor1D<T> {
+    /// Tensor of `n` elements, every one produced by `f`. Unlike `from_values`, `n` can be 0.
+    fn generate(n: usize, mut f: impl FnMut(usize) -> T) -> Tensor1D<T> {
+        let out = Tensor1D::zeros(n);
+        out.borrow()
+            .iter()
+            .enumerate()
+            .for_each(|(i, x)| x.borrow_mut().data = f(i));
+        out
+    }
+
+    pub fn ones(n: usize) -> Tensor1D<T> {
+        Tensor1D::full(n, T::one())
+    }
+
+    pub fn full(n: usize, value: T) -> Tensor1D<T> {
+        Tensor1D::generate(n, |_| value)
+    }
+
+    /// `start, start + step, ...` up to `end`, which is not included. Same as NumPy,
+    /// the tensor is empty if `end` can't be reached.
+    pub fn arange(start: T, end: T, step: T) -> Tensor1D<T> {
+        assert!(step != T::zero(), "step of arange can't be zero");
+        let n = ((end - start) / step).to_f64().ceil().max(0.0) as usize;
+        Tensor1D::generate(n, |i| start + step * T::from_f64(i as f64))
+    }
+
+    /// `n` evenly spaced values from `start` to `end`, both included.
+    pub fn linspace(start: T, end: T, n: usize) -> Tensor1D<T> {
+        let step = (end - start) / T::from_f64(n.saturating_sub(1).max(1) as f64);
+        Tensor1D::generate(n, |i| start + step * T::from_f64(i as f64))
+    }
+
+    /// Samples from `[low, high)`.
+    pub fn rand_uniform<R: Rng + ?Sized>(n: usize, low: T, high: T, rng: &mut R) -> Tensor1D<T> {
+        let (low, high) = (low.to_f64(), high.to_f64());
+        Tensor1D::generate(n, |_| T::from_f64(uniform(rng, low, high)))
+    }
+
+    /// Samples from the standard normal distribution.
+    pub fn randn<R: Rng + ?Sized>(n: usize, rng: &mut R) -> Tensor1D<T> {
+        Tensor1D::generate(n, |_| T::from_f64(normal(rng, 1.0)))
+    }
+}
+
+impl<T: Scalar> Tensor2D<T> {
+    /// Matrix of `r` rows, every element produced by `f`. Either size can be 0.
+    fn generate(r: usize, c: usize, mut f: impl FnMut(usize, usize) -> T) -> Tensor2D<T> {
+        let out = Tensor2D::zeros(r, c);
+        out.borrow().iter().enumerate().for_each(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .for_each(|(j, x)| x.borrow_mut().data = f(i, j))
+        });
+        out
+    }
+
+    pub fn ones(r: usize, c: usize) -> Tensor2D<T> {
+        Tensor2D::full(r, c, T::one())
+    }
+
+    pub fn full(r: usize, c: usize, value: T) -> Tensor2D<T> {
+        Tensor2D::generate(r, c, |_, _| value)
+    }
+
+    /// `n x n` identity matrix.
+    pub fn eye(n: usize) -> Tensor2D<T> {
+        Tensor2D::generate(n, n, |i, j| if i == j { T::one() } else { T::zero() })
+    }
+
+    /// Samples from `[low, high)`.
+    pub fn rand_uniform<R: Rng + ?Sized>(
+        r: usize,
+        c: usize,
+        low: T,
+        high: T,
+        rng: &mut R,
+    ) -> Tensor2D<T> {
+        let (low, high) = (low.to_f64(), high.to_f64());
+        Tensor2D::generate(r, c, |_, _| T::from_f64(uniform(rng, low, high)))
+    }
+
+    /// Samples from the standard normal distribution.
+    pub fn randn<R: Rng + ?Sized>(r: usize, c: usize, rng: &mut R) -> Tensor2D<T> {
+        Tensor2D::generate(r, c, |_, _| T::from_f64(normal(rng, 1.0)))
+    }
+
+    /// Xavier/Glorot uniform init of a weight `W` used as `W * x`:
+    /// `fan_in = c`, `fan_out = r`, samples from `[-a, a)` with `a = sqrt(6 / (fan_in + fan_out))`.
+    pub fn xavier_uniform<R: Rng + ?Sized>(r: usize, c: usize, rng: &mut R) -> Tensor2D<T> {
+        let a = (6.0 / (r + c) as f64).sqrt();
+        Tensor2D::generate(r, c, |_, _| T::from_f64(uniform(rng, -a, a)))
+    }
+
+    /// Xavier/Glorot normal init, see `xavier_uniform`: std is `sqrt(2 / (fan_in + fan_out))`.
+    pub fn xavier_normal<R: Rng + ?Sized>(r: usize, c: usize, rng: &mut R) -> Tensor2D<T> {
+        let std = (2.0 / (r + c) as f64).sqrt();
+        Tensor2D::generate(r, c, |_, _| T::from_f64(normal(rng, std)))
+    }
+
+    /// Kaiming/He uniform init for ReLU layers, see `xavier_uniform`: `a = sqrt(6 / fan_in)`.
+    pub fn kaiming_uniform<R: Rng + ?Sized>(r: usize, c: usize, rng: &mut R) -> Tensor2D<T> {
+        let a = (6.0 / c as f64).sqrt();
+        Tensor2D::generate(r, c, |_, _| T::from_f64(uniform(rng, -a, a)))
+    }
+
+    /// Kaiming/He normal init for ReLU layers, see `xavier_uniform`: std is `sqrt(2 / fan_in)`.
+    pub fn kaiming_normal<R: Rng + ?Sized>(r: usize, c: usize, rng: &mut R) -> Tensor2D<T> {
+        let std = (2.0 / c as f64).sqrt();
+        Tensor2D::generate(r, c, |_, _| T::from_f64(normal(rng, std)))
+    }
+}
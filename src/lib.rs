@@ -1,10 +1,39 @@
-#![feature(core_intrinsics)]
+#[macro_use]
+mod utils;
+pub use crate::utils::{max, min, Scalar};
 
 mod variable;
-pub use crate::variable::{Operation, Variable, VariableData};
+pub use crate::variable::{BackwardFn, Operation, Variable, VariableData};
+
+mod custom_op;
+pub use crate::custom_op::CustomOp;
+
+mod dot;
+
+mod grad;
+pub use crate::grad::grad;
+
+mod no_grad;
+pub use crate::no_grad::{is_grad_enabled, no_grad, NoGradGuard};
+
+mod dual;
+pub use crate::dual::{jvp, Dual};
+
+mod functional;
+pub use crate::functional::{hessian, jacobian};
+
+mod gradcheck;
+pub use crate::gradcheck::{gradcheck, GradCheckError, Leaf, Leaves, Output};
 
 mod tensor;
 pub use crate::tensor::{Tensor1D, Tensor2D};
 
-mod utils;
-pub use crate::utils::max;
+mod gemm;
+
+mod init;
+
+mod random;
+pub use crate::random::{manual_seed, shuffle, with_rng};
+
+mod nd_tensor;
+pub use crate::nd_tensor::{Tensor, TensorBackwardFn, TensorData, TensorOp};
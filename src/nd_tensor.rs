@@ -0,0 +1,553 @@
+use crate::{
+    gemm::{matmul, transpose},
+    no_grad::is_grad_enabled,
+    utils::{max, Scalar},
+    variable::next_id,
+    Operation,
+};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    ops::{Add, Deref, Div, Mul, Neg, Sub},
+    rc::Rc,
+};
+
+/// N-dimensional tensor with contiguous row-major storage.
+///
+/// Unlike `Tensor1D` and `Tensor2D`, the whole tensor is a single graph node,
+/// so every op records one node and runs one vectorised backward.
+#[derive(Default, Clone)]
+pub struct Tensor<T: Scalar = f64>(pub Rc<RefCell<TensorData<T>>>);
+
+/// Kind of the op that produced a `Tensor` node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TensorOp {
+    /// Elementwise op or reduction, same as the one of a `Variable`.
+    Op(Operation),
+    /// Matrix product, see `Tensor::matmul`.
+    MatMul,
+    /// Transpose of a 2D tensor.
+    Transpose,
+    /// Same data under a new shape.
+    Reshape,
+}
+
+/// Propagates `grad` of a tensor node to its children.
+pub type TensorBackwardFn<T = f64> = Box<dyn Fn(&TensorData<T>)>;
+
+pub struct TensorData<T: Scalar = f64> {
+    pub data: Vec<T>,
+    pub grad: Vec<T>,
+    pub shape: Vec<usize>,
+    pub id: usize,
+    pub fun: Option<TensorBackwardFn<T>>,
+    pub op: Option<TensorOp>,
+    pub children: Vec<Tensor<T>>,
+}
+
+impl<T: Scalar> Default for TensorData<T> {
+    /// Scalar tensor (shape `[]`) holding zero, with a fresh id.
+    fn default() -> Self {
+        TensorData {
+            data: vec![T::zero()],
+            grad: vec![T::zero()],
+            shape: Vec::new(),
+            id: next_id(),
+            fun: None,
+            op: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl<T: Scalar> Drop for TensorData<T> {
+    /// Tears the graph down iteratively, same as `VariableData`.
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.children);
+        while let Some(child) = stack.pop() {
+            if Rc::strong_count(&child) == 1 {
+                stack.append(&mut child.borrow_mut().children);
+            }
+        }
+    }
+}
+
+impl<T: Scalar> Deref for Tensor<T> {
+    type Target = Rc<RefCell<TensorData<T>>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Step in the data for every axis: `strides[i]` is the product of `shape[i + 1..]`.
+fn strides_of(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for i in (0..shape.len().saturating_sub(1)).rev() {
+        strides[i] = strides[i + 1] * shape[i + 1];
+    }
+    strides
+}
+
+/// Shape of an elementwise op of tensors with shapes `a` and `b`, same as NumPy:
+/// shapes are aligned from the last axis, and an axis of size 1 or a missing one
+/// is repeated to match the other operand.
+fn broadcast_shape(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let n = a.len().max(b.len());
+    let dim = |shape: &[usize], i: usize| (i + shape.len()).checked_sub(n).map_or(1, |j| shape[j]);
+    (0..n)
+        .map(|i| {
+            let (x, y) = (dim(a, i), dim(b, i));
+            assert!(
+                x == y || x == 1 || y == 1,
+                "can't broadcast shapes {:?} and {:?}",
+                a,
+                b
+            );
+            if x == 1 {
+                y
+            } else {
+                x
+            }
+        })
+        .collect()
+}
+
+/// Position in the data of a tensor of shape `shape` for every element of
+/// its broadcast to `out`, see `broadcast_shape`.
+fn broadcast_index(shape: &[usize], out: &[usize]) -> Vec<usize> {
+    let offset = out.len() - shape.len();
+    let strides = strides_of(shape);
+    // repeated axes don't move in the data
+    let steps = (0..out.len())
+        .map(|i| match i.checked_sub(offset) {
+            Some(j) if shape[j] != 1 => strides[j],
+            _ => 0,
+        })
+        .collect::<Vec<_>>();
+    let out_strides = strides_of(out);
+    (0..out.iter().product())
+        .map(|pos: usize| {
+            out_strides
+                .iter()
+                .zip(out)
+                .zip(&steps)
+                .map(|((&s, &n), &step)| pos / s % n * step)
+                .sum()
+        })
+        .collect()
+}
+
+impl<T: Scalar> Tensor<T> {
+    /// Tensor of the given shape, `data` is in row-major order.
+    pub fn from_vec(data: Vec<T>, shape: &[usize]) -> Tensor<T> {
+        assert_eq!(
+            data.len(),
+            shape.iter().product::<usize>(),
+            "{} values don't fit into shape {:?}",
+            data.len(),
+            shape
+        );
+        let out = Tensor::default();
+        {
+            let mut inner = out.borrow_mut();
+            inner.grad = vec![T::zero(); data.len()];
+            inner.data = data;
+            inner.shape = shape.to_vec();
+        }
+        out
+    }
+
+    pub fn zeros(shape: &[usize]) -> Tensor<T> {
+        Tensor::full(shape, T::zero())
+    }
+
+    pub fn full(shape: &[usize], value: T) -> Tensor<T> {
+        Tensor::from_vec(vec![value; shape.iter().product()], shape)
+    }
+
+    pub fn new(tensor: TensorData<T>) -> Tensor<T> {
+        Tensor(Rc::new(RefCell::new(tensor)))
+    }
+
+    /// Creates the result node of an op, see `Variable::from_op`.
+    fn from_op(
+        data: Vec<T>,
+        shape: &[usize],
+        op: TensorOp,
+        children: impl FnOnce() -> Vec<Tensor<T>>,
+        fun: impl Fn(&TensorData<T>) + 'static,
+    ) -> Tensor<T> {
+        let out = Tensor::from_vec(data, shape);
+        if is_grad_enabled() {
+            let mut inner = out.borrow_mut();
+            inner.op = Some(op);
+            inner.children = children();
+            inner.fun = Some(Box::new(fun));
+        }
+        out
+    }
+
+    pub fn shape(&self) -> Vec<usize> {
+        self.borrow().shape.clone()
+    }
+
+    /// Step in the data for every axis, the storage is always contiguous.
+    pub fn strides(&self) -> Vec<usize> {
+        strides_of(&self.borrow().shape)
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.borrow().data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn data(&self) -> Vec<T> {
+        self.borrow().data.clone()
+    }
+
+    pub fn grad(&self) -> Vec<T> {
+        self.borrow().grad.clone()
+    }
+
+    /// Element at the given multi-index.
+    pub fn get(&self, index: &[usize]) -> T {
+        let inner = self.borrow();
+        assert_eq!(index.len(), inner.shape.len(), "wrong number of indices");
+        let pos = index
+            .iter()
+            .zip(inner.shape.iter().zip(strides_of(&inner.shape)))
+            .map(|(&i, (&n, s))| {
+                assert!(i < n, "index {} is out of bounds for axis of size {}", i, n);
+                i * s
+            })
+            .sum::<usize>();
+        inner.data[pos]
+    }
+
+    /// The only value of a tensor with a single element, e.g. a loss.
+    pub fn item(&self) -> T {
+        assert_eq!(self.len(), 1, "only single-element tensors have an item");
+        self.borrow().data[0]
+    }
+
+    fn add_grad(&self, grad: &[T]) {
+        self.borrow_mut()
+            .grad
+            .iter_mut()
+            .zip(grad)
+            .for_each(|(g, &d)| *g += d);
+    }
+
+    /// Backpropagates from `self`, accumulating into the grads of the leaves.
+    ///
+    /// The grad of `self` is seeded with ones.
+    pub fn backward(&self) {
+        let order = self.topological_order();
+        order.iter().for_each(|it| {
+            let mut node = it.borrow_mut();
+            if node.fun.is_some() {
+                node.grad.iter_mut().for_each(|g| *g = T::zero());
+            }
+        });
+        self.borrow_mut()
+            .grad
+            .iter_mut()
+            .for_each(|g| *g = T::one());
+        order.into_iter().rev().for_each(|it| {
+            if let Some(fun) = &it.borrow().fun {
+                fun(&it.borrow());
+            }
+        });
+    }
+
+    /// Every node reachable from `self`, children before parents.
+    fn topological_order(&self) -> Vec<Tensor<T>> {
+        let (mut order, mut used) = (Vec::new(), HashSet::new());
+        let mut stack = vec![(self.clone(), false)];
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                order.push(node);
+                continue;
+            }
+            if !used.insert(node.borrow().id) {
+                continue;
+            }
+            stack.push((node.clone(), true));
+            node.borrow().children.iter().for_each(|child| {
+                if !used.contains(&child.borrow().id) {
+                    stack.push((child.clone(), false));
+                }
+            });
+        }
+        order
+    }
+
+    /// for gradient descent
+    pub fn zero_grad(&self) {
+        self.borrow_mut()
+            .grad
+            .iter_mut()
+            .for_each(|g| *g = T::zero());
+    }
+
+    pub fn step(&self, lr: T) {
+        let mut inner = self.borrow_mut();
+        let TensorData { data, grad, .. } = &mut *inner;
+        data.iter_mut()
+            .zip(grad.iter())
+            .for_each(|(x, &g)| *x -= lr * g);
+    }
+
+    /// Elementwise op. `df(x, y)` is the derivative at input `x` with output `y`.
+    fn map(
+        &self,
+        op: Operation,
+        f: impl Fn(T) -> T,
+        df: impl Fn(T, T) -> T + 'static,
+    ) -> Tensor<T> {
+        let data = self.borrow().data.iter().map(|&x| f(x)).collect();
+        Tensor::from_op(
+            data,
+            &self.shape(),
+            TensorOp::Op(op),
+            || vec![self.clone()],
+            move |x: &TensorData<T>| {
+                let grad = x.children[0]
+                    .borrow()
+                    .data
+                    .iter()
+                    .zip(x.data.iter().zip(&x.grad))
+                    .map(|(&a, (&y, &g))| g * df(a, y))
+                    .collect::<Vec<_>>();
+                x.children[0].add_grad(&grad);
+            },
+        )
+    }
+
+    pub fn pow(&self, p: T) -> Tensor<T> {
+        self.map(
+            Operation::Pow(p.to_f64()),
+            |x| x.powf(p),
+            move |x, _| x.powf(p - T::one()) * p,
+        )
+    }
+
+    pub fn sin(&self) -> Tensor<T> {
+        self.map(Operation::Sin, |x| x.sin(), |x, _| x.cos())
+    }
+
+    pub fn cos(&self) -> Tensor<T> {
+        self.map(Operation::Cos, |x| x.cos(), |x, _| -x.sin())
+    }
+
+    pub fn exp(&self) -> Tensor<T> {
+        self.map(Operation::Exp, |x| x.exp(), |_, y| y)
+    }
+
+    pub fn ln(&self) -> Tensor<T> {
+        self.map(Operation::Ln, |x| x.ln(), |x, _| T::one() / x)
+    }
+
+    pub fn relu(&self) -> Tensor<T> {
+        self.map(
+            Operation::Relu,
+            |x| max(x, T::zero()),
+            |x, _| if x > T::zero() { T::one() } else { T::zero() },
+        )
+    }
+
+    /// Elementwise op of two tensors, whose shapes are broadcast, see `broadcast_shape`.
+    /// `df(a, b, grad)` gives the grads of both inputs; the grad of a repeated element
+    /// is summed over its repeats.
+    fn zip_with(
+        &self,
+        other: &Tensor<T>,
+        op: Operation,
+        f: impl Fn(T, T) -> T,
+        df: impl Fn(T, T, T) -> (T, T) + 'static,
+    ) -> Tensor<T> {
+        let (a_shape, b_shape) = (self.shape(), other.shape());
+        let shape = broadcast_shape(&a_shape, &b_shape);
+        let (ia, ib) = (
+            broadcast_index(&a_shape, &shape),
+            broadcast_index(&b_shape, &shape),
+        );
+        let data = {
+            let (a, b) = (self.borrow(), other.borrow());
+            ia.iter()
+                .zip(&ib)
+                .map(|(&i, &j)| f(a.data[i], b.data[j]))
+                .collect()
+        };
+        Tensor::from_op(
+            data,
+            &shape,
+            TensorOp::Op(op),
+            || vec![self.clone(), other.clone()],
+            move |x: &TensorData<T>| {
+                let (da, db) = {
+                    let (a, b) = (x.children[0].borrow(), x.children[1].borrow());
+                    let (mut da, mut db) =
+                        (vec![T::zero(); a.data.len()], vec![T::zero(); b.data.len()]);
+                    for ((&i, &j), &g) in ia.iter().zip(&ib).zip(&x.grad) {
+                        let (ga, gb) = df(a.data[i], b.data[j], g);
+                        da[i] += ga;
+                        db[j] += gb;
+                    }
+                    (da, db)
+                };
+                x.children[0].add_grad(&da);
+                x.children[1].add_grad(&db);
+            },
+        )
+    }
+
+    /// Sum of all the elements, as a tensor of shape `[]`.
+    pub fn sum(&self) -> Tensor<T> {
+        let sum = self.borrow().data.iter().copied().sum();
+        Tensor::from_op(
+            vec![sum],
+            &[],
+            TensorOp::Op(Operation::Sum),
+            || vec![self.clone()],
+            |x: &TensorData<T>| {
+                let child = &x.children[0];
+                let n = child.len();
+                child.add_grad(&vec![x.grad[0]; n]);
+            },
+        )
+    }
+
+    pub fn mean(&self) -> Tensor<T> {
+        self.sum() / T::from_f64(self.len() as f64)
+    }
+
+    /// Matrix product of `[n, k]` with `[k, m]` or with a vector `[k]`.
+    ///
+    /// Backward computes `dA = dC B^T` and `dB = A^T dC` in one go.
+    pub fn matmul(&self, other: &Tensor<T>) -> Tensor<T> {
+        let (a_shape, b_shape) = (self.shape(), other.shape());
+        assert_eq!(a_shape.len(), 2, "left operand of matmul must be 2D");
+        let (n, k) = (a_shape[0], a_shape[1]);
+        let m = match b_shape[..] {
+            [rows, m] if rows == k => m,
+            [rows] if rows == k => 1,
+            _ => panic!("can't multiply {:?} by {:?}", a_shape, b_shape),
+        };
+        let shape = if b_shape.len() == 2 {
+            vec![n, m]
+        } else {
+            vec![n]
+        };
+        let data = matmul(&self.borrow().data, &other.borrow().data, n, k, m);
+        Tensor::from_op(
+            data,
+            &shape,
+            TensorOp::MatMul,
+            || vec![self.clone(), other.clone()],
+            move |x: &TensorData<T>| {
+                let b_t = transpose(&x.children[1].borrow().data, k, m);
+                let a_t = transpose(&x.children[0].borrow().data, n, k);
+                x.children[0].add_grad(&matmul(&x.grad, &b_t, n, m, k));
+                x.children[1].add_grad(&matmul(&a_t, &x.grad, k, n, m));
+            },
+        )
+    }
+
+    /// Transpose of a 2D tensor.
+    pub fn t(&self) -> Tensor<T> {
+        let shape = self.shape();
+        assert_eq!(shape.len(), 2, "only 2D tensors can be transposed");
+        let (r, c) = (shape[0], shape[1]);
+        let data = transpose(&self.borrow().data, r, c);
+        Tensor::from_op(
+            data,
+            &[c, r],
+            TensorOp::Transpose,
+            || vec![self.clone()],
+            move |x: &TensorData<T>| {
+                x.children[0].add_grad(&transpose(&x.grad, c, r));
+            },
+        )
+    }
+
+    /// Same elements in the same order under a new shape.
+    pub fn reshape(&self, shape: &[usize]) -> Tensor<T> {
+        Tensor::from_op(
+            self.data(),
+            shape,
+            TensorOp::Reshape,
+            || vec![self.clone()],
+            |x: &TensorData<T>| x.children[0].add_grad(&x.grad),
+        )
+    }
+}
+
+impl<T: Scalar> Add<&Tensor<T>> for &Tensor<T> {
+    type Output = Tensor<T>;
+
+    fn add(self, b: &Tensor<T>) -> Tensor<T> {
+        self.zip_with(b, Operation::ADD, |x, y| x + y, |_, _, g| (g, g))
+    }
+}
+
+impl<T: Scalar> Mul<&Tensor<T>> for &Tensor<T> {
+    type Output = Tensor<T>;
+
+    fn mul(self, b: &Tensor<T>) -> Tensor<T> {
+        self.zip_with(b, Operation::MUL, |x, y| x * y, |x, y, g| (g * y, g * x))
+    }
+}
+
+impl<T: Scalar> Div<&Tensor<T>> for &Tensor<T> {
+    type Output = Tensor<T>;
+
+    fn div(self, b: &Tensor<T>) -> Tensor<T> {
+        self.zip_with(
+            b,
+            Operation::DIV,
+            |x, y| x / y,
+            |x, y, g| (g / y, -(g / y) * (x / y)),
+        )
+    }
+}
+
+impl<T: Scalar> Neg for &Tensor<T> {
+    type Output = Tensor<T>;
+
+    fn neg(self) -> Tensor<T> {
+        self * -T::one()
+    }
+}
+
+impl<T: Scalar> Sub<&Tensor<T>> for &Tensor<T> {
+    type Output = Tensor<T>;
+
+    fn sub(self, b: &Tensor<T>) -> Tensor<T> {
+        self + -b
+    }
+}
+
+/// `tensor op number`, the number is turned into a constant tensor of shape `[]`.
+macro_rules! impl_tensor_scalar {
+    ($($imp:ident, $method:ident);*) => {$(
+        impl<T: Scalar> $imp<T> for &Tensor<T> {
+            type Output = Tensor<T>;
+
+            fn $method(self, b: T) -> Tensor<T> {
+                self.$method(&Tensor::from_vec(vec![b], &[]))
+            }
+        }
+
+        forward_binop!(impl $imp, $method for Tensor<T>, Tensor<T> => Tensor<T>);
+        forward_binop_scalar!(impl $imp, $method for Tensor<T> => Tensor<T>);
+    )*};
+}
+
+impl_tensor_scalar!(Add, add; Sub, sub; Mul, mul; Div, div);
+forward_unop!(impl Neg, neg for Tensor<T>);
@@ -0,0 +1,42 @@
+use std::cell::Cell;
+
+thread_local! {
+    static GRAD_ENABLED: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Returns false inside of `no_grad` or while a `NoGradGuard` is alive.
+pub fn is_grad_enabled() -> bool {
+    GRAD_ENABLED.with(|x| x.get())
+}
+
+/// Disables graph construction on the current thread until dropped.
+///
+/// Ops only compute `data`, so inference doesn't keep the graph alive.
+pub struct NoGradGuard {
+    prev: bool,
+}
+
+impl NoGradGuard {
+    pub fn new() -> NoGradGuard {
+        let prev = GRAD_ENABLED.with(|x| x.replace(false));
+        NoGradGuard { prev }
+    }
+}
+
+impl Default for NoGradGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for NoGradGuard {
+    fn drop(&mut self) {
+        GRAD_ENABLED.with(|x| x.set(self.prev));
+    }
+}
+
+/// Runs `f` without recording the graph.
+pub fn no_grad<R>(f: impl FnOnce() -> R) -> R {
+    let _guard = NoGradGuard::new();
+    f()
+}
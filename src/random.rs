@@ -0,0 +1,39 @@
+use crate::utils::Scalar;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::cell::RefCell;
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Seeds the RNG of the current thread, used by `with_rng`, `shuffle` and `dropout`.
+///
+/// Two runs with the same seed draw the same numbers, so weights and losses are bit-identical.
+/// Unless seeded, the RNG starts from OS entropy.
+pub fn manual_seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Runs `f` with the RNG of the current thread, see `manual_seed`.
+///
+/// `with_rng(|rng| Tensor2D::xavier_uniform(3, 4, rng))` draws the initial weights from it.
+pub fn with_rng<R>(f: impl FnOnce(&mut StdRng) -> R) -> R {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+/// Shuffles `v` in place with the RNG of the current thread.
+pub fn shuffle<T>(v: &mut [T]) {
+    with_rng(|rng| v.shuffle(rng))
+}
+
+/// Factor of one element in inverted dropout: `0` with probability `p`, `1 / (1 - p)` otherwise.
+pub(crate) fn dropout_scale<T: Scalar>(p: T) -> T {
+    let p = p.to_f64();
+    assert!(
+        (0.0..1.0).contains(&p),
+        "dropout probability must be in [0, 1), got {}",
+        p
+    );
+    let keep = with_rng(|rng| rng.gen::<f64>()) >= p;
+    T::from_f64(if keep { 1.0 / (1.0 - p) } else { 0.0 })
+}
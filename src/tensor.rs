@@ -1,12 +1,18 @@
-use crate::{Operation, Variable, VariableData};
-use auto_ops::*;
-use std::{cell::RefCell, ops::Deref, rc::Rc};
-
-/// Equvalent of R^d. One column
+use crate::{
+    gemm, no_grad::is_grad_enabled, random, utils::Scalar, Operation, Variable, VariableData,
+};
+use std::{
+    cell::RefCell,
+    ops::{Add, Deref, Div, Mul, Neg, Range, Sub},
+    rc::Rc,
+};
+
+/// Equvalent of R^d. A row when broadcast against a `Tensor2D`, same as NumPy,
+/// but a column on the right of a matrix product: `Tensor2D * Tensor1D`.
 #[derive(Default)]
-pub struct Tensor1D(pub Rc<RefCell<Vec<Variable>>>, pub usize);
-impl Deref for Tensor1D {
-    type Target = Rc<RefCell<Vec<Variable>>>;
+pub struct Tensor1D<T: Scalar = f64>(pub Rc<RefCell<Vec<Variable<T>>>>, pub usize);
+impl<T: Scalar> Deref for Tensor1D<T> {
+    type Target = Rc<RefCell<Vec<Variable<T>>>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -15,137 +21,366 @@ impl Deref for Tensor1D {
 
 /// Equvalent to matrix: R^(a * b)
 #[derive(Default)]
-pub struct Tensor2D(pub Rc<RefCell<Vec<Vec<Variable>>>>, pub (usize, usize));
+pub struct Tensor2D<T: Scalar = f64>(pub Rc<RefCell<Vec<Vec<Variable<T>>>>>, pub (usize, usize));
 
-impl Deref for Tensor2D {
-    type Target = Rc<RefCell<Vec<Vec<Variable>>>>;
+impl<T: Scalar> Deref for Tensor2D<T> {
+    type Target = Rc<RefCell<Vec<Vec<Variable<T>>>>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl_op_ex!(*|a: &Tensor2D, b: &Tensor1D| -> Tensor1D {
-    assert_eq!(a.1 .1, b.1);
-    let out = Tensor1D::new(a.1 .0);
-    for i in 0..(a.1 .0) {
-        let mut c = Variable::from(0.0);
-        for k in 0..(a.1 .1) {
-            c = c + &a.0.borrow()[i][k] * &b.0.borrow()[k];
-        }
-        out.0.borrow_mut()[i] = c;
-    }
-    out
-});
-
-impl_op_ex!(*|a: &Tensor2D, b: &Tensor2D| -> Tensor2D {
-    assert_eq!(a.1 .1, b.1 .0);
-    let out = Tensor2D::new(a.1 .0, b.1 .1);
-    for i in 0..(a.1 .0) {
-        for j in 0..(b.1 .1) {
-            let mut c = Variable::from(0.0);
-            for k in 0..(a.1 .1) {
-                c = c + &a.0.borrow()[i][k] * &b.0.borrow()[k][j];
-            }
-            out.0.borrow_mut()[i][j] = c;
-        }
+/// Matrix product of `a` (`n x k`) and `b` (`k x m`), given as rows and shapes.
+///
+/// All entries of `a` and `b` are children of a single hub node, followed by one
+/// `MatMulGrad` node per output entry. Every output entry has the hub as its only child
+/// and adds its grad to its `MatMulGrad` node, then the backward of the hub reads `dC`
+/// from those nodes and computes `dA = dC B^T` and `dB = A^T dC` at once.
+fn matmul<T: Scalar>(
+    a: &[Vec<Variable<T>>],
+    b: &[Vec<Variable<T>>],
+    (n, k): (usize, usize),
+    (b_rows, m): (usize, usize),
+) -> Vec<Vec<Variable<T>>> {
+    assert_eq!(
+        b_rows,
+        k,
+        "can't multiply {:?} by {:?}",
+        (n, k),
+        (b_rows, m)
+    );
+    let values = a
+        .iter()
+        .chain(b)
+        .flatten()
+        .map(|x| x.data())
+        .collect::<Vec<_>>();
+    let c = gemm::matmul(&values[..n * k], &values[n * k..], n, k, m);
+    if !is_grad_enabled() {
+        // no hub: the entries are plain leaves
+        return (0..n)
+            .map(|i| (0..m).map(|j| Variable::from_value(c[i * m + j])).collect())
+            .collect();
+    }
+
+    // `dC` is part of the graph, so every backward pass resets it like any other grad
+    let d_c_offset = n * k + k * m;
+    let hub = Variable::from_op(
+        T::zero(),
+        Operation::MatMulHub { n, k, m },
+        || {
+            let d_c = (0..n).flat_map(|row| {
+                (0..m).map(move |col| {
+                    Variable::from_op(
+                        T::zero(),
+                        Operation::MatMulGrad { row, col },
+                        Vec::new,
+                        |_: &VariableData<T>| {},
+                    )
+                })
+            });
+            a.iter().chain(b).flatten().cloned().chain(d_c).collect()
+        },
+        move |x: &VariableData<T>| {
+            let (operands, d_c) = x.children.split_at(d_c_offset);
+            let values = operands.iter().map(|c| c.data()).collect::<Vec<_>>();
+            let (a, b) = values.split_at(n * k);
+            let d_c = d_c.iter().map(|c| c.grad()).collect::<Vec<_>>();
+            let d_a = gemm::matmul(&d_c, &gemm::transpose(b, k, m), n, m, k);
+            let d_b = gemm::matmul(&gemm::transpose(a, n, k), &d_c, k, n, m);
+            operands
+                .iter()
+                .zip(d_a.into_iter().chain(d_b))
+                .for_each(|(child, g)| child.borrow_mut().grad += g);
+        },
+    );
+    (0..n)
+        .map(|i| {
+            (0..m)
+                .map(|j| {
+                    Variable::from_op(
+                        c[i * m + j],
+                        Operation::MatMul { row: i, col: j },
+                        || vec![hub.clone()],
+                        move |x: &VariableData<T>| {
+                            let hub = x.children[0].borrow();
+                            hub.children[d_c_offset + i * m + j].borrow_mut().grad += x.grad;
+                        },
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+impl<T: Scalar> Mul<&Tensor1D<T>> for &Tensor2D<T> {
+    type Output = Tensor1D<T>;
+
+    fn mul(self, b: &Tensor1D<T>) -> Tensor1D<T> {
+        let column = b
+            .borrow()
+            .iter()
+            .map(|x| vec![x.clone()])
+            .collect::<Vec<_>>();
+        let out = matmul(&self.borrow(), &column, self.1, (b.1, 1));
+        Tensor1D::from_rows(vec![out.into_iter().flatten().collect()], self.1 .0)
     }
-    out
-});
+}
 
-impl_op_ex_commutative!(*|a: &Tensor2D, b: &Variable| -> Tensor2D {
-    let out = Tensor2D::new(a.1 .0, a.1 .1);
-    for i in 0..(a.1 .0) {
-        for j in 0..(a.1 .1) {
-            out.borrow_mut()[i][j] = &a.0.borrow()[i][j] * b;
-        }
+impl<T: Scalar> Mul<&Tensor2D<T>> for &Tensor2D<T> {
+    type Output = Tensor2D<T>;
+
+    fn mul(self, b: &Tensor2D<T>) -> Tensor2D<T> {
+        let out = matmul(&self.borrow(), &b.borrow(), self.1, b.1);
+        Tensor2D::from_rows(out, b.1 .1)
     }
-    out
-});
+}
 
-impl_op_ex_commutative!(*|a: &Tensor1D, b: &Variable| -> Tensor1D {
-    let out = Tensor1D::new(a.1);
-    for i in 0..(a.1) {
-        let x = &a.borrow()[i];
-        out.borrow_mut()[i] = x * b;
+/// Operand of a broadcasting op, seen as a matrix.
+///
+/// Same as NumPy: a `Tensor1D` of length `n` is a `1 x n` row and a `Variable` is `1 x 1`.
+trait Broadcast<T: Scalar> {
+    fn rows(&self) -> Vec<Vec<Variable<T>>>;
+
+    /// Shape of the matrix, kept apart from `rows` since a matrix may have no rows.
+    fn dims(&self) -> (usize, usize);
+}
+
+impl<T: Scalar> Broadcast<T> for Variable<T> {
+    fn rows(&self) -> Vec<Vec<Variable<T>>> {
+        vec![vec![self.clone()]]
     }
-    out
-});
 
-impl_op_ex!(+|a: &Tensor2D, b: &Tensor2D| -> Tensor2D {
-    assert_eq!(a.1, b.1);
-    let out = Tensor2D::new(a.1 .0, b.1 .1);
-    for i in 0..(a.1 .0) {
-        for j in 0..(a.1 .1) {
-            out.0.borrow_mut()[i][j] = &a.0.borrow()[i][j] * &b.0.borrow()[j][j];
-        }
+    fn dims(&self) -> (usize, usize) {
+        (1, 1)
     }
-    out
-});
+}
 
-impl_op_ex_commutative!(+|a: &Tensor2D, b: &Variable| -> Tensor2D {
-    let out = Tensor2D::new(a.1 .0, a.1 .1);
-    for i in 0..(a.1 .0) {
-        for j in 0..(a.1 .1) {
-            let x = &a.borrow()[i][j];
-            out.borrow_mut()[i][j] = x + b;
-        }
+impl<T: Scalar> Broadcast<T> for Tensor1D<T> {
+    fn rows(&self) -> Vec<Vec<Variable<T>>> {
+        vec![self.borrow().clone()]
     }
-    out
-});
-
-impl_op_ex_commutative!(+|a: &Tensor1D, b: &Variable| -> Tensor1D {
-    let out = Tensor1D::new(a.1);
-    for i in 0..(a.1) {
-        let x = &a.borrow()[i];
-        out.borrow_mut()[i] = x + b;
-    }
-    out
-});
-
-impl_op_ex!(+|a: &Tensor1D, b: &Tensor1D| -> Tensor1D {
-    let out = Tensor1D::new(a.1);
-    for i in 0..(a.1) {
-        let x = &a.borrow()[i];
-        let y = &b.borrow()[i];
-        out.borrow_mut()[i] = x + y;
-    }
-    out
-});
-
-impl_op_ex!(-|a: &Tensor2D| -> Tensor2D {
-    let out = Tensor2D::new(a.1 .0, a.1 .1);
-    for i in 0..(a.1 .0) {
-        for j in 0..(a.1 .1) {
-            let x = &a.borrow()[i][j];
-            out.borrow_mut()[i][j] = x * -1.;
-        }
+
+    fn dims(&self) -> (usize, usize) {
+        (1, self.1)
     }
-    out
-});
+}
 
-impl_op_ex!(-|a: &Tensor1D, b: &Tensor1D| -> Tensor1D { a + -b });
+impl<T: Scalar> Broadcast<T> for Tensor2D<T> {
+    fn rows(&self) -> Vec<Vec<Variable<T>>> {
+        self.borrow().clone()
+    }
 
-// TODO!!!!!!
-impl_op_ex!(-|a: &Tensor1D| -> Tensor1D {
-    let out = Tensor1D::new(a.1);
-    for i in 0..(a.1) {
-        let x = &a.borrow()[i];
-        out.borrow_mut()[i] = -x;
+    fn dims(&self) -> (usize, usize) {
+        self.1
+    }
+}
+
+/// Applies `f` to every pair of elements, repeating axes of size 1 to match the other operand.
+///
+/// A broadcast element is a child of several output nodes, so its grad is summed
+/// over the repeated axis by the backward pass itself.
+///
+/// Returns the rows of the output and its number of columns.
+fn broadcast<T: Scalar>(
+    a: &impl Broadcast<T>,
+    b: &impl Broadcast<T>,
+    f: impl Fn(&Variable<T>, &Variable<T>) -> Variable<T>,
+) -> (Vec<Vec<Variable<T>>>, usize) {
+    let (a_shape, b_shape) = (a.dims(), b.dims());
+    let dim = |x: usize, y: usize| {
+        assert!(
+            x == y || x == 1 || y == 1,
+            "can't broadcast shapes {:?} and {:?}",
+            a_shape,
+            b_shape
+        );
+        // same as NumPy: an axis of size 1 against an empty one is empty
+        if x == 1 {
+            y
+        } else {
+            x
+        }
+    };
+    let (r, c) = (dim(a_shape.0, b_shape.0), dim(a_shape.1, b_shape.1));
+    let at = |m: &[Vec<Variable<T>>], i: usize, j: usize| -> Variable<T> {
+        let row = &m[if m.len() == 1 { 0 } else { i }];
+        row[if row.len() == 1 { 0 } else { j }].clone()
+    };
+    let (a, b) = (a.rows(), b.rows());
+    let rows = (0..r)
+        .map(|i| (0..c).map(|j| f(&at(&a, i, j), &at(&b, i, j))).collect())
+        .collect();
+    (rows, c)
+}
+
+impl<T: Scalar> Tensor1D<T> {
+    /// From the only row of a `1 x n` matrix.
+    fn from_rows(mut rows: Vec<Vec<Variable<T>>>, n: usize) -> Tensor1D<T> {
+        let row = rows.remove(0);
+        Tensor1D(Rc::new(RefCell::new(row)), n)
     }
-    out
-});
+}
 
-impl_op_ex!(-|a: &Variable, b: &Tensor2D| -> Tensor2D { a + -b });
-impl_op_ex!(-|a: &Tensor2D, b: &Variable| -> Tensor2D { a + -b });
+impl<T: Scalar> Tensor2D<T> {
+    /// The number of columns is passed along, as there is no row to take it from
+    /// when `rows` is empty.
+    fn from_rows(rows: Vec<Vec<Variable<T>>>, c: usize) -> Tensor2D<T> {
+        let shape = (rows.len(), c);
+        Tensor2D(Rc::new(RefCell::new(rows)), shape)
+    }
+}
 
-impl_op_ex!(/|a: &Tensor2D, b: &Variable| -> Tensor2D { a * (1.0 / b) });
-impl_op_ex!(/|a: &Tensor1D, b: &Variable| -> Tensor1D { a * (1.0 / b) });
+/// Elementwise ops with broadcasting for the given `lhs, rhs => output` combinations.
+macro_rules! impl_broadcast {
+    (impl $imp:ident, $method:ident, |$x:ident, $y:ident| $body:expr; $($lhs:ident, $rhs:ident => $out:ident);*) => {$(
+        impl<T: Scalar> $imp<&$rhs<T>> for &$lhs<T> {
+            type Output = $out<T>;
 
-// TODO: use impl_op_commutative!()
+            fn $method(self, b: &$rhs<T>) -> $out<T> {
+                let (rows, c) = broadcast(self, b, |$x, $y| $body);
+                $out::from_rows(rows, c)
+            }
+        }
+
+        forward_binop!(impl $imp, $method for $lhs<T>, $rhs<T> => $out<T>);
+    )*};
+}
+
+/// `tensor op number` and `number op tensor`, the number is turned into a `Variable`.
+macro_rules! impl_broadcast_scalar {
+    ($($imp:ident, $method:ident);*) => {$(
+        impl_broadcast_scalar!(@tensor $imp, $method, Tensor1D);
+        impl_broadcast_scalar!(@tensor $imp, $method, Tensor2D);
+        impl_broadcast_scalar!(@lhs $imp, $method, f32);
+        impl_broadcast_scalar!(@lhs $imp, $method, f64);
+    )*};
+    (@tensor $imp:ident, $method:ident, $tensor:ident) => {
+        impl<T: Scalar> $imp<T> for &$tensor<T> {
+            type Output = $tensor<T>;
+
+            fn $method(self, b: T) -> $tensor<T> {
+                self.$method(&Variable::from_value(b))
+            }
+        }
+
+        forward_binop_scalar!(impl $imp, $method for $tensor<T> => $tensor<T>);
+    };
+    (@lhs $imp:ident, $method:ident, $t:ty) => {
+        impl_broadcast_scalar!(@lhs_tensor $imp, $method, $t, Tensor1D);
+        impl_broadcast_scalar!(@lhs_tensor $imp, $method, $t, Tensor2D);
+    };
+    (@lhs_tensor $imp:ident, $method:ident, $t:ty, $tensor:ident) => {
+        impl $imp<&$tensor<$t>> for $t {
+            type Output = $tensor<$t>;
+
+            fn $method(self, b: &$tensor<$t>) -> $tensor<$t> {
+                Variable::from_value(self).$method(b)
+            }
+        }
+
+        impl $imp<$tensor<$t>> for $t {
+            type Output = $tensor<$t>;
+
+            fn $method(self, b: $tensor<$t>) -> $tensor<$t> {
+                Variable::from_value(self).$method(&b)
+            }
+        }
+    };
+}
+
+impl_broadcast!(impl Add, add, |x, y| x + y;
+    Tensor1D, Tensor1D => Tensor1D;
+    Tensor2D, Tensor2D => Tensor2D;
+    Tensor2D, Tensor1D => Tensor2D;
+    Tensor1D, Tensor2D => Tensor2D;
+    Tensor1D, Variable => Tensor1D;
+    Variable, Tensor1D => Tensor1D;
+    Tensor2D, Variable => Tensor2D;
+    Variable, Tensor2D => Tensor2D);
+
+impl_broadcast!(impl Sub, sub, |x, y| x - y;
+    Tensor1D, Tensor1D => Tensor1D;
+    Tensor2D, Tensor2D => Tensor2D;
+    Tensor2D, Tensor1D => Tensor2D;
+    Tensor1D, Tensor2D => Tensor2D;
+    Tensor1D, Variable => Tensor1D;
+    Variable, Tensor1D => Tensor1D;
+    Tensor2D, Variable => Tensor2D;
+    Variable, Tensor2D => Tensor2D);
+
+impl_broadcast!(impl Div, div, |x, y| x / y;
+    Tensor1D, Tensor1D => Tensor1D;
+    Tensor2D, Tensor2D => Tensor2D;
+    Tensor2D, Tensor1D => Tensor2D;
+    Tensor1D, Tensor2D => Tensor2D;
+    Tensor1D, Variable => Tensor1D;
+    Variable, Tensor1D => Tensor1D;
+    Tensor2D, Variable => Tensor2D;
+    Variable, Tensor2D => Tensor2D);
+
+// `Tensor2D * Tensor2D` and `Tensor2D * Tensor1D` are matrix products, see above.
+// `Tensor1D * Tensor2D` is neither: for the row-broadcast product use `m.hadamard(&x.t())`.
+impl_broadcast!(impl Mul, mul, |x, y| x * y;
+    Tensor1D, Tensor1D => Tensor1D;
+    Tensor1D, Variable => Tensor1D;
+    Variable, Tensor1D => Tensor1D;
+    Tensor2D, Variable => Tensor2D;
+    Variable, Tensor2D => Tensor2D);
+
+impl_broadcast_scalar!(Add, add; Sub, sub; Mul, mul; Div, div);
+
+impl<T: Scalar> Neg for &Tensor2D<T> {
+    type Output = Tensor2D<T>;
+
+    fn neg(self) -> Tensor2D<T> {
+        let a = self;
+        let out = Tensor2D::zeros(a.1 .0, a.1 .1);
+        for i in 0..(a.1 .0) {
+            for j in 0..(a.1 .1) {
+                let x = &a.borrow()[i][j];
+                out.borrow_mut()[i][j] = x * -T::one();
+            }
+        }
+        out
+    }
+}
+
+// TODO!!!!!!
+impl<T: Scalar> Neg for &Tensor1D<T> {
+    type Output = Tensor1D<T>;
+
+    fn neg(self) -> Tensor1D<T> {
+        let a = self;
+        let out = Tensor1D::zeros(a.1);
+        for i in 0..(a.1) {
+            let x = &a.borrow()[i];
+            out.borrow_mut()[i] = -x;
+        }
+        out
+    }
+}
+
+forward_binop!(impl Mul, mul for Tensor2D<T>, Tensor1D<T> => Tensor1D<T>);
+forward_binop!(impl Mul, mul for Tensor2D<T>, Tensor2D<T> => Tensor2D<T>);
+forward_unop!(impl Neg, neg for Tensor2D<T>);
+forward_unop!(impl Neg, neg for Tensor1D<T>);
 
 impl Tensor1D {
     pub fn new(n: usize) -> Tensor1D {
+        Tensor1D::zeros(n)
+    }
+
+    #[allow(clippy::ptr_arg)]
+    pub fn from(v: &Vec<f64>) -> Tensor1D {
+        Tensor1D::from_values(v)
+    }
+}
+
+impl<T: Scalar> Tensor1D<T> {
+    /// Same as `Tensor1D::new`, but for any precision.
+    pub fn zeros(n: usize) -> Tensor1D<T> {
         Tensor1D(
             Rc::new(RefCell::new(
                 (0..n).map(|_| Variable::default()).collect::<Vec<_>>(),
@@ -158,10 +393,9 @@ impl Tensor1D {
         (self.1,)
     }
 
-    pub fn from(v: &Vec<f64>) -> Tensor1D {
-        assert!(!v.is_empty(), "can't create empty tensor2D");
-
-        let out = Self::new(v.len());
+    /// Same as `Tensor1D::from`, but for any precision.
+    pub fn from_values(v: &[T]) -> Tensor1D<T> {
+        let out = Self::zeros(v.len());
         out.borrow_mut()
             .iter_mut()
             .zip(v.iter())
@@ -173,8 +407,8 @@ impl Tensor1D {
     }
 
     /// transforms Tensor1D(_, d) to Tensor2D(_, (1, d))
-    pub fn t(&self) -> Tensor2D {
-        let out = Tensor2D::new(1, self.1);
+    pub fn t(&self) -> Tensor2D<T> {
+        let out = Tensor2D::zeros(1, self.1);
         out.borrow_mut()[0]
             .iter_mut()
             .zip(self.borrow().iter())
@@ -185,7 +419,7 @@ impl Tensor1D {
     }
 
     /// If Tensor1D represents a single Variable, it can be casted
-    pub fn cast(&self) -> Variable {
+    pub fn cast(&self) -> Variable<T> {
         assert_eq!(
             self.1, 1,
             "Tensor1D must have exactly one element to be casted to Variable."
@@ -198,8 +432,8 @@ impl Tensor1D {
         self.cast().backward();
     }
 
-    pub fn apply_fn(&self, fun: fn(&Variable) -> Variable) -> Tensor1D {
-        let out = Tensor1D::new(self.1);
+    fn map(&self, fun: impl Fn(&Variable<T>) -> Variable<T>) -> Tensor1D<T> {
+        let out = Tensor1D::zeros(self.1);
         let current = self.0.borrow();
 
         // Apply the function to each element and fill the output tensor
@@ -213,75 +447,179 @@ impl Tensor1D {
         out
     }
 
-    pub fn sin(&self) -> Tensor1D {
+    pub fn apply_fn(&self, fun: fn(&Variable<T>) -> Variable<T>) -> Tensor1D<T> {
+        self.map(fun)
+    }
+
+    pub fn sin(&self) -> Tensor1D<T> {
         self.apply_fn(|x| x.sin())
     }
 
-    pub fn cos(&self) -> Tensor1D {
+    pub fn cos(&self) -> Tensor1D<T> {
         self.apply_fn(|x| x.cos())
     }
 
-    pub fn silu(&self) -> Tensor1D {
+    pub fn silu(&self) -> Tensor1D<T> {
         self.apply_fn(|x| x.silu())
     }
 
-    pub fn relu(&self) -> Tensor1D {
+    pub fn relu(&self) -> Tensor1D<T> {
         self.apply_fn(|x| x.relu())
     }
 
-    pub fn exp(&self) -> Tensor1D {
+    pub fn exp(&self) -> Tensor1D<T> {
         self.apply_fn(|x| x.exp())
     }
 
-    /// returns (Variable) - the sum of all interior elements
-    pub fn sum(&self) -> Variable {
+    /// returns (Variable) - the sum of all interior elements, `0` for an empty tensor
+    pub fn sum(&self) -> Variable<T> {
         // We want to avoid creating a long graph.
         // So tensor elements will be the childen of the resulting node.
-        let out = Variable::from(self.borrow().iter().map(|x| x.data()).sum::<f64>());
-        out.borrow_mut().op = Some(Operation::Custom(String::from("sum1D")));
-        out.borrow_mut().children = self.borrow().clone();
-        out.borrow_mut().fun = Some(|x: &VariableData| {
-            let grad = x.grad;
+        Variable::from_op(
+            self.borrow().iter().map(|x| x.data()).sum::<T>(),
+            Operation::Sum,
+            || self.borrow().clone(),
+            |x: &VariableData<T>| {
+                let grad = x.grad;
+                x.children.iter().for_each(|child| {
+                    child.borrow_mut().grad += grad;
+                })
+            },
+        )
+    }
+
+    pub fn pow(&self, p: T) -> Tensor1D<T> {
+        self.map(|x| x.pow(p))
+    }
+
+    /// Single node with all the elements as children, like `sum`.
+    fn reduce(&self, op: Operation, fun: impl Fn(&VariableData<T>) + 'static) -> Variable<T> {
+        let values = self.borrow().iter().map(|x| x.data()).collect::<Vec<_>>();
+        Variable::from_op(op.eval(&values).unwrap(), op, || self.borrow().clone(), fun)
+    }
+
+    /// NaN for an empty tensor, as `0 / 0`.
+    pub fn mean(&self) -> Variable<T> {
+        self.reduce(Operation::Mean, |x: &VariableData<T>| {
+            let grad = x.grad / T::from_f64(x.children.len() as f64);
             x.children.iter().for_each(|child| {
                 child.borrow_mut().grad += grad;
             })
-        });
+        })
+    }
 
-        out
+    /// Largest element. Only the first of equal maximums gets the grad.
+    pub fn max(&self) -> Variable<T> {
+        assert!(self.1 > 0, "can't take the max of an empty tensor");
+        self.reduce(Operation::Max, |x: &VariableData<T>| {
+            if let Some(child) = x.children.iter().find(|c| c.data() == x.data) {
+                child.borrow_mut().grad += x.grad;
+            }
+        })
     }
 
-    /// TODO: avoiding copy-pasting
-    pub fn pow(&self, p: f64) -> Tensor1D {
-        let out = Tensor1D::new(self.1);
-        let current = self.0.borrow();
-        out.borrow_mut()
-            .iter_mut()
-            .zip(current.iter())
-            .for_each(|(o, v)| *o = v.pow(p));
-        out
+    /// Smallest element. Only the first of equal minimums gets the grad.
+    pub fn min(&self) -> Variable<T> {
+        assert!(self.1 > 0, "can't take the min of an empty tensor");
+        self.reduce(Operation::Min, |x: &VariableData<T>| {
+            if let Some(child) = x.children.iter().find(|c| c.data() == x.data) {
+                child.borrow_mut().grad += x.grad;
+            }
+        })
+    }
+
+    /// Population variance: `mean((x - mean(x))^2)`, NaN for an empty tensor.
+    pub fn var(&self) -> Variable<T> {
+        self.reduce(Operation::Var, |x: &VariableData<T>| {
+            let n = T::from_f64(x.children.len() as f64);
+            let mean = x.children.iter().map(|c| c.data()).sum::<T>() / n;
+            let scale = x.grad * T::from_f64(2.0) / n;
+            x.children.iter().for_each(|child| {
+                let d = child.data() - mean;
+                child.borrow_mut().grad += scale * d;
+            })
+        })
     }
 
-    pub fn mean(&self) -> Variable {
-        self.sum() / (self.1 as f64)
+    /// Square root of `var`.
+    ///
+    /// For equal elements `std` isn't differentiable, their grads are taken as zero.
+    pub fn std(&self) -> Variable<T> {
+        self.reduce(Operation::Std, |x: &VariableData<T>| {
+            if x.data == T::zero() {
+                return;
+            }
+            let n = T::from_f64(x.children.len() as f64);
+            let mean = x.children.iter().map(|c| c.data()).sum::<T>() / n;
+            let scale = x.grad / (n * x.data);
+            x.children.iter().for_each(|child| {
+                let d = child.data() - mean;
+                child.borrow_mut().grad += scale * d;
+            })
+        })
     }
 
-    pub fn softmax(&self) -> Tensor1D {
-        let x = self.clone().exp();
-        &x / x.sum()
+    /// `ln(exp(x1) + ... + exp(xn))`, as a single node.
+    ///
+    /// The max is subtracted before `exp`, so large logits don't overflow.
+    /// NaN for an empty tensor, like `mean`; `softmax` of it is just empty.
+    pub fn logsumexp(&self) -> Variable<T> {
+        self.reduce(Operation::LogSumExp, |x: &VariableData<T>| {
+            x.children.iter().for_each(|child| {
+                let p = (child.data() - x.data).exp();
+                child.borrow_mut().grad += x.grad * p;
+            })
+        })
+    }
+
+    /// `exp(x) / sum(exp(x))`, computed as `exp(x - logsumexp(x))`.
+    ///
+    /// Every output is a single node with two children: its element and the shared
+    /// `logsumexp` node. It stays finite for logits in the thousands.
+    pub fn softmax(&self) -> Tensor1D<T> {
+        let lse = self.logsumexp();
+        self.map(|x| {
+            Variable::from_op(
+                (x.data() - lse.data()).exp(),
+                Operation::Softmax,
+                || vec![x.clone(), lse.clone()],
+                |x: &VariableData<T>| {
+                    let grad = x.grad * x.data;
+                    x.children[0].borrow_mut().grad += grad;
+                    x.children[1].borrow_mut().grad -= grad;
+                },
+            )
+        })
+    }
+
+    /// `ln(softmax(x))`, computed as `x - logsumexp(x)`, see `softmax`.
+    pub fn log_softmax(&self) -> Tensor1D<T> {
+        let lse = self.logsumexp();
+        self.map(|x| {
+            Variable::from_op(
+                x.data() - lse.data(),
+                Operation::LogSoftmax,
+                || vec![x.clone(), lse.clone()],
+                |x: &VariableData<T>| {
+                    x.children[0].borrow_mut().grad += x.grad;
+                    x.children[1].borrow_mut().grad -= x.grad;
+                },
+            )
+        })
     }
 
     /// (x1, ..., xn) -> x1^2 + ... + xn^2
-    pub fn l2_2(&self) -> Variable {
-        self.pow(2.0).sum()
+    pub fn l2_2(&self) -> Variable<T> {
+        self.pow(T::from_f64(2.0)).sum()
     }
 
     /// (x1, ..., xn) -> sqrt(x1^2 + ... + xn^2)
-    pub fn l2_norm(&self) -> Variable {
-        self.l2_2().pow(0.5)
+    pub fn l2_norm(&self) -> Variable<T> {
+        self.l2_2().pow(T::from_f64(0.5))
     }
 
     /// (x1, ..., xn), (y1, ..., yn) -> (x1-y1)^2 + ... + (xn-yn)^2
-    pub fn mse_loss(&self, other: &Tensor1D) -> Variable {
+    pub fn mse_loss(&self, other: &Tensor1D<T>) -> Variable<T> {
         assert!(
             self.shape() == other.shape(),
             "1D shapes must be equal to use it"
@@ -289,32 +627,110 @@ impl Tensor1D {
         (self - other).l2_2()
     }
 
-    /// (p1, ..., pn) -> -p1*ln(p1) - ... - pn*ln(pn)
-    pub fn cross_entropy_loss(&self, other: &Tensor1D) -> Variable {
+    /// (p1, ..., pn), (q1, ..., qn) -> -p1*ln(q1) - ... - pn*ln(qn)
+    pub fn cross_entropy_loss(&self, other: &Tensor1D<T>) -> Variable<T> {
+        assert!(
+            self.shape() == other.shape(),
+            "1D shapes must be equal to use it"
+        );
+        -self.dot(&other.ln())
+    }
+
+    /// -(p1 * log_softmax(x)1 + ... + pn * log_softmax(x)n) of the targets `self` and the logits `x`.
+    ///
+    /// Same as `cross_entropy_loss(&x.softmax())`, but stays finite for large logits.
+    pub fn cross_entropy_with_logits(&self, logits: &Tensor1D<T>) -> Variable<T> {
+        assert!(
+            self.shape() == logits.shape(),
+            "1D shapes must be equal to use it"
+        );
+        -self.dot(&logits.log_softmax())
+    }
+
+    /// (x1, ..., xn), (y1, ..., yn) -> (x1*y1, ..., xn*yn)
+    ///
+    /// Same as `*`: a tensor of length 1 is broadcast.
+    pub fn hadamard(&self, other: &Tensor1D<T>) -> Tensor1D<T> {
+        self * other
+    }
+
+    /// (x1, ..., xn), (y1, ..., yn) -> x1*y1 + ... + xn*yn
+    ///
+    /// A single node with the elements of both tensors as children.
+    pub fn dot(&self, other: &Tensor1D<T>) -> Variable<T> {
         assert!(
             self.shape() == other.shape(),
             "1D shapes must be equal to use it"
         );
-        -self.hadamard_product(&other.ln())
+        let (a, b) = (self.borrow(), other.borrow());
+        let values = a
+            .iter()
+            .chain(b.iter())
+            .map(|x| x.data())
+            .collect::<Vec<_>>();
+        Variable::from_op(
+            Operation::Dot.eval(&values).unwrap(),
+            Operation::Dot,
+            || a.iter().chain(b.iter()).cloned().collect(),
+            |x: &VariableData<T>| {
+                let (a, b) = x.children.split_at(x.children.len() / 2);
+                a.iter().zip(b).for_each(|(a, b)| {
+                    let (da, db) = (x.grad * b.data(), x.grad * a.data());
+                    a.borrow_mut().grad += da;
+                    b.borrow_mut().grad += db;
+                })
+            },
+        )
     }
 
-    /// actually, not optimal, TODO
-    pub fn hadamard_product(&self, other: &Tensor1D) -> Variable {
-        (other.t() * self).cast()
+    /// (x1, ..., xn), (y1, ..., ym) -> n x m matrix of xi*yj
+    pub fn outer(&self, other: &Tensor1D<T>) -> Tensor2D<T> {
+        let column = self
+            .borrow()
+            .iter()
+            .map(|x| vec![x.clone()])
+            .collect::<Vec<_>>();
+        let (rows, c) = broadcast(&Tensor2D::from_rows(column, 1), other, |x, y| x * y);
+        Tensor2D::from_rows(rows, c)
     }
 
-    pub fn ln(&self) -> Tensor1D {
+    /// Same as `dot`: despite the name, this is not an elementwise product.
+    #[deprecated(note = "use dot")]
+    pub fn hadamard_product(&self, other: &Tensor1D<T>) -> Variable<T> {
+        self.dot(other)
+    }
+
+    pub fn ln(&self) -> Tensor1D<T> {
         self.apply_fn(|x| x.ln())
     }
+
+    /// Zeroes every element with probability `p` and scales the rest by `1 / (1 - p)`,
+    /// so the expectation is unchanged. The mask is drawn from the crate RNG, see `manual_seed`.
+    ///
+    /// Only meant for training: skip it when evaluating the model.
+    pub fn dropout(&self, p: T) -> Tensor1D<T> {
+        self.map(|x| x * random::dropout_scale(p))
+    }
 }
 
 impl Tensor2D {
+    pub fn new(r: usize, c: usize) -> Tensor2D {
+        Tensor2D::zeros(r, c)
+    }
+
+    #[allow(clippy::ptr_arg)]
+    pub fn from(v: &Vec<Vec<f64>>) -> Tensor2D {
+        Tensor2D::from_values(v)
+    }
+}
+
+impl<T: Scalar> Tensor2D<T> {
     /// Resurns default matrix, filled with zero.
     ///
     /// We can't actually use vec![T::default(); _] here, because of cloning rc.
     ///
     /// todo: something smarter?
-    pub fn new(r: usize, c: usize) -> Tensor2D {
+    pub fn zeros(r: usize, c: usize) -> Tensor2D<T> {
         let v = (0..r)
             .map(|_| (0..c).map(|_| Variable::default()).collect::<Vec<_>>())
             .collect::<Vec<_>>();
@@ -325,10 +741,10 @@ impl Tensor2D {
         self.1
     }
 
-    pub fn from(v: &Vec<Vec<f64>>) -> Tensor2D {
-        assert!(!v.is_empty(), "can't create empty tensor2D");
-        let (r, c) = (v.len(), v[0].len());
-        let out = Self::new(r, c);
+    /// Same as `Tensor2D::from`, but for any precision.
+    pub fn from_values(v: &[Vec<T>]) -> Tensor2D<T> {
+        let (r, c) = (v.len(), v.first().map_or(0, Vec::len));
+        let out = Self::zeros(r, c);
         // trying to avoid double `.borrow_mut()` every step.
         // {  ...  } - to make compiler happy :)
         {
@@ -343,28 +759,215 @@ impl Tensor2D {
         out
     }
 
-    pub fn sum(&self) -> Variable {
+    pub fn sum(&self) -> Variable<T> {
         // Same strategy as in Tensor1D
-        let out = Variable::from(
-            self.borrow()
+        Variable::from_op(
+            self.borrow().iter().flatten().map(|x| x.data()).sum::<T>(),
+            Operation::Sum,
+            || self.borrow().iter().flatten().cloned().collect::<Vec<_>>(),
+            |x: &VariableData<T>| {
+                let grad = x.grad;
+                x.children.iter().for_each(|child| {
+                    child.borrow_mut().grad += grad;
+                })
+            },
+        )
+    }
+
+    /// NaN for an empty matrix, see `Tensor1D::mean`.
+    pub fn mean(&self) -> Variable<T> {
+        let flat = self.borrow().iter().flatten().cloned().collect::<Vec<_>>();
+        Tensor1D::from_rows(vec![flat], self.1 .0 * self.1 .1).mean()
+    }
+
+    fn map(&self, fun: impl Fn(&Variable<T>) -> Variable<T>) -> Tensor2D<T> {
+        let rows = self
+            .borrow()
+            .iter()
+            .map(|row| row.iter().map(&fun).collect())
+            .collect();
+        Tensor2D::from_rows(rows, self.1 .1)
+    }
+
+    pub fn apply_fn(&self, fun: fn(&Variable<T>) -> Variable<T>) -> Tensor2D<T> {
+        self.map(fun)
+    }
+
+    pub fn sin(&self) -> Tensor2D<T> {
+        self.apply_fn(|x| x.sin())
+    }
+
+    pub fn cos(&self) -> Tensor2D<T> {
+        self.apply_fn(|x| x.cos())
+    }
+
+    pub fn silu(&self) -> Tensor2D<T> {
+        self.apply_fn(|x| x.silu())
+    }
+
+    pub fn relu(&self) -> Tensor2D<T> {
+        self.apply_fn(|x| x.relu())
+    }
+
+    pub fn exp(&self) -> Tensor2D<T> {
+        self.apply_fn(|x| x.exp())
+    }
+
+    pub fn ln(&self) -> Tensor2D<T> {
+        self.apply_fn(|x| x.ln())
+    }
+
+    pub fn pow(&self, p: T) -> Tensor2D<T> {
+        self.map(|x| x.pow(p))
+    }
+
+    /// Rows of `self` for `axis == 1`, columns for `axis == 0`.
+    fn lanes(&self, axis: usize) -> Vec<Tensor1D<T>> {
+        match axis {
+            0 => (0..self.1 .1).map(|j| self.col(j)).collect(),
+            1 => (0..self.1 .0).map(|i| self.row(i)).collect(),
+            _ => panic!("Tensor2D has no axis {}", axis),
+        }
+    }
+
+    fn reduce_axis(&self, axis: usize, fun: fn(&Tensor1D<T>) -> Variable<T>) -> Tensor1D<T> {
+        let out = self.lanes(axis).iter().map(fun).collect::<Vec<_>>();
+        let n = out.len();
+        Tensor1D::from_rows(vec![out], n)
+    }
+
+    /// Sums over `axis`: `0` reduces the rows away (one value per column),
+    /// `1` reduces the columns (one value per row).
+    ///
+    /// Every output element is a single node, see `Tensor1D::sum`.
+    pub fn sum_axis(&self, axis: usize) -> Tensor1D<T> {
+        self.reduce_axis(axis, |x| x.sum())
+    }
+
+    /// See `sum_axis`.
+    pub fn mean_axis(&self, axis: usize) -> Tensor1D<T> {
+        self.reduce_axis(axis, |x| x.mean())
+    }
+
+    /// See `sum_axis` and `Tensor1D::max`.
+    pub fn max_axis(&self, axis: usize) -> Tensor1D<T> {
+        self.reduce_axis(axis, |x| x.max())
+    }
+
+    /// See `sum_axis` and `Tensor1D::min`.
+    pub fn min_axis(&self, axis: usize) -> Tensor1D<T> {
+        self.reduce_axis(axis, |x| x.min())
+    }
+
+    /// Population variance, see `sum_axis`.
+    pub fn var_axis(&self, axis: usize) -> Tensor1D<T> {
+        self.reduce_axis(axis, |x| x.var())
+    }
+
+    /// See `sum_axis` and `var_axis`.
+    pub fn std_axis(&self, axis: usize) -> Tensor1D<T> {
+        self.reduce_axis(axis, |x| x.std())
+    }
+
+    /// `softmax` of every row.
+    pub fn softmax(&self) -> Tensor2D<T> {
+        Tensor2D::from_rows(
+            self.lanes(1)
                 .iter()
-                .flatten()
-                .map(|x| x.data())
-                .sum::<f64>(),
+                .map(|x| x.softmax().borrow().clone())
+                .collect(),
+            self.1 .1,
+        )
+    }
+
+    /// `log_softmax` of every row.
+    pub fn log_softmax(&self) -> Tensor2D<T> {
+        Tensor2D::from_rows(
+            self.lanes(1)
+                .iter()
+                .map(|x| x.log_softmax().borrow().clone())
+                .collect(),
+            self.1 .1,
+        )
+    }
+
+    /// `logsumexp` of every row.
+    pub fn logsumexp(&self) -> Tensor1D<T> {
+        self.reduce_axis(1, |x| x.logsumexp())
+    }
+
+    /// Transposed matrix.
+    ///
+    /// Views share `Variable`s with `self`: no new graph nodes are created and
+    /// grads of the view flow into the original elements.
+    pub fn t(&self) -> Tensor2D<T> {
+        let inner = self.borrow();
+        let rows = (0..self.1 .1)
+            .map(|j| inner.iter().map(|row| row[j].clone()).collect())
+            .collect();
+        Tensor2D::from_rows(rows, self.1 .0)
+    }
+
+    /// Row `i` as a view, see `Tensor2D::t`.
+    pub fn row(&self, i: usize) -> Tensor1D<T> {
+        assert!(i < self.1 .0, "row {} is out of bounds for {:?}", i, self.1);
+        let row = self.borrow()[i].clone();
+        Tensor1D(Rc::new(RefCell::new(row)), self.1 .1)
+    }
+
+    /// Column `j` as a view, see `Tensor2D::t`.
+    pub fn col(&self, j: usize) -> Tensor1D<T> {
+        assert!(
+            j < self.1 .1,
+            "column {} is out of bounds for {:?}",
+            j,
+            self.1
         );
-        out.borrow_mut().op = Some(Operation::Custom(String::from("sum2D")));
-        out.borrow_mut().children = self.borrow().iter().flatten().cloned().collect::<Vec<_>>();
-        out.borrow_mut().fun = Some(|x: &VariableData| {
-            let grad = x.grad;
-            x.children.iter().for_each(|child| {
-                child.borrow_mut().grad += grad;
-            })
-        });
+        let col = self.borrow().iter().map(|row| row[j].clone()).collect();
+        Tensor1D(Rc::new(RefCell::new(col)), self.1 .0)
+    }
 
-        out
+    /// Sub-matrix as a view, see `Tensor2D::t`.
+    pub fn slice(&self, rows: Range<usize>, cols: Range<usize>) -> Tensor2D<T> {
+        assert!(
+            rows.end <= self.1 .0 && cols.end <= self.1 .1,
+            "slice {:?} x {:?} is out of bounds for {:?}",
+            rows,
+            cols,
+            self.1
+        );
+        let rows = self.borrow()[rows]
+            .iter()
+            .map(|row| row[cols.clone()].to_vec())
+            .collect();
+        Tensor2D::from_rows(rows, cols.len())
+    }
+
+    /// The same elements in row-major order, as an `r x c` view, see `Tensor2D::t`.
+    pub fn reshape(&self, r: usize, c: usize) -> Tensor2D<T> {
+        assert_eq!(
+            r * c,
+            self.1 .0 * self.1 .1,
+            "can't reshape {:?} into {:?}",
+            self.1,
+            (r, c)
+        );
+        let flat = self.borrow().iter().flatten().cloned().collect::<Vec<_>>();
+        let rows = (0..r).map(|i| flat[i * c..(i + 1) * c].to_vec()).collect();
+        Tensor2D::from_rows(rows, c)
+    }
+
+    /// Elementwise product. `*` is the matrix product, so this one is a method.
+    ///
+    /// Shapes are broadcast the same way as in `+`: `m.hadamard(&x.t())` scales
+    /// every row by a `Tensor1D`.
+    pub fn hadamard(&self, other: &Tensor2D<T>) -> Tensor2D<T> {
+        let (rows, c) = broadcast(self, other, |x, y| x * y);
+        Tensor2D::from_rows(rows, c)
     }
 
-    pub fn mean(&self) -> Variable {
-        self.sum() / ((self.1 .0) * (self.1 .1)) as f64
+    /// Same as `Tensor1D::dropout`, with a mask over all the elements.
+    pub fn dropout(&self, p: T) -> Tensor2D<T> {
+        self.map(|x| x * random::dropout_scale(p))
     }
 }
@@ -1,6 +1,180 @@
-pub fn max(x: f64, y: f64) -> f64 {
+use std::{
+    fmt::{Debug, Display},
+    iter::Sum,
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
+};
+
+pub fn max<T: PartialOrd>(x: T, y: T) -> T {
     if x < y {
         return y;
     }
     x
 }
+
+pub fn min<T: PartialOrd>(x: T, y: T) -> T {
+    if y < x {
+        return y;
+    }
+    x
+}
+
+pub(crate) fn mean<T: Scalar>(x: &[T]) -> T {
+    x.iter().copied().sum::<T>() / T::from_f64(x.len() as f64)
+}
+
+/// Population variance.
+pub(crate) fn var<T: Scalar>(x: &[T]) -> T {
+    let m = mean(x);
+    mean(&x.iter().map(|&v| (v - m) * (v - m)).collect::<Vec<_>>())
+}
+
+/// `ln(sum(exp(x)))` without overflow: the max is subtracted before `exp`.
+///
+/// NaN for an empty `x`, like `mean`.
+pub(crate) fn logsumexp<T: Scalar>(x: &[T]) -> T {
+    let Some(&first) = x.first() else {
+        return T::zero() / T::zero();
+    };
+    let m = x.iter().copied().fold(first, max);
+    m + x.iter().map(|&v| (v - m).exp()).sum::<T>().ln()
+}
+
+/// Floating point type stored in a `Variable`: `f32` or `f64`.
+pub trait Scalar:
+    Copy
+    + Default
+    + Debug
+    + Display
+    + PartialOrd
+    + Sum
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + Send
+    + Sync
+    + 'static
+{
+    fn from_f64(x: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn powf(self, p: Self) -> Self;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+
+    fn zero() -> Self {
+        Self::from_f64(0.0)
+    }
+
+    fn one() -> Self {
+        Self::from_f64(1.0)
+    }
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty),*) => {$(
+        impl Scalar for $t {
+            fn from_f64(x: f64) -> Self {
+                x as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn powf(self, p: Self) -> Self {
+                <$t>::powf(self, p)
+            }
+
+            fn sqrt(self) -> Self {
+                <$t>::sqrt(self)
+            }
+
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+
+            fn sin(self) -> Self {
+                <$t>::sin(self)
+            }
+
+            fn cos(self) -> Self {
+                <$t>::cos(self)
+            }
+
+            fn exp(self) -> Self {
+                <$t>::exp(self)
+            }
+
+            fn ln(self) -> Self {
+                <$t>::ln(self)
+            }
+        }
+    )*};
+}
+
+impl_scalar!(f32, f64);
+
+/// Implements a binary operator for every owned/borrowed combination of operands,
+/// forwarding to the `&lhs op &rhs` impl.
+///
+/// Does the same as `auto_ops::impl_op_ex`, which can't handle types generic over `T: Scalar`.
+macro_rules! forward_binop {
+    (impl $imp:ident, $method:ident for $lhs:ty, $rhs:ty => $out:ty) => {
+        impl<T: Scalar> $imp<$rhs> for $lhs {
+            type Output = $out;
+
+            fn $method(self, rhs: $rhs) -> $out {
+                (&self).$method(&rhs)
+            }
+        }
+
+        impl<T: Scalar> $imp<&$rhs> for $lhs {
+            type Output = $out;
+
+            fn $method(self, rhs: &$rhs) -> $out {
+                (&self).$method(rhs)
+            }
+        }
+
+        impl<T: Scalar> $imp<$rhs> for &$lhs {
+            type Output = $out;
+
+            fn $method(self, rhs: $rhs) -> $out {
+                self.$method(&rhs)
+            }
+        }
+    };
+}
+
+/// Owned counterpart of the `&lhs op T` impl, where `T: Scalar` is a plain number.
+macro_rules! forward_binop_scalar {
+    (impl $imp:ident, $method:ident for $lhs:ty => $out:ty) => {
+        impl<T: Scalar> $imp<T> for $lhs {
+            type Output = $out;
+
+            fn $method(self, rhs: T) -> $out {
+                (&self).$method(rhs)
+            }
+        }
+    };
+}
+
+/// Owned counterpart of the `op &value` impl.
+macro_rules! forward_unop {
+    (impl $imp:ident, $method:ident for $ty:ty) => {
+        impl<T: Scalar> $imp for $ty {
+            type Output = $ty;
+
+            fn $method(self) -> $ty {
+                (&self).$method()
+            }
+        }
+    };
+}
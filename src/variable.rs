@@ -1,253 +1,546 @@
-use crate::utils::max;
-use auto_ops::*;
-use rand::prelude::*;
+use crate::{
+    no_grad::is_grad_enabled,
+    utils::{logsumexp, max, mean, min, var, Scalar},
+};
 
 use std::{
     cell::RefCell,
     collections::HashSet,
     fmt::Debug,
     hash::Hash,
-    intrinsics::{cosf64, expf64, logf64, sinf64},
-    ops::Deref,
+    ops::{Add, Deref, Div, Mul, Neg, Sub},
     rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
+/// Source of node ids. Every `VariableData` takes the next value, so ids never collide.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn next_id() -> usize {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Node of the graph, holding a scalar of type `T` (`f64` unless stated otherwise).
 #[derive(Default, Clone)]
-pub struct Variable(pub Rc<RefCell<VariableData>>);
+pub struct Variable<T: Scalar = f64>(pub Rc<RefCell<VariableData<T>>>);
 
-#[derive(Debug, Clone)]
+/// Kind of the op that produced a node, together with its parameters.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Operation {
     ADD,
     MUL,
     DIV,
+    /// x^p
+    Pow(f64),
+    Sin,
+    Cos,
+    Exp,
+    Ln,
+    Tan,
+    Relu,
+    /// Sum of all the children, produced by `Tensor1D::sum` and `Tensor2D::sum`.
+    Sum,
+    /// Mean of all the children.
+    Mean,
+    /// Largest of the children, the grad goes to the first one of them.
+    Max,
+    /// Smallest of the children, the grad goes to the first one of them.
+    Min,
+    /// Population variance of the children (divided by `n`, not `n - 1`).
+    Var,
+    /// Square root of `Var`.
+    Std,
+    /// `ln(sum(exp(x)))` of all the children, computed with the max subtracted.
+    LogSumExp,
+    /// `exp(x - lse)` of an element and the `LogSumExp` of its tensor.
+    Softmax,
+    /// `x - lse` of an element and the `LogSumExp` of its tensor.
+    LogSoftmax,
+    /// `x1 y1 + ... + xn yn` of the children `x1, ..., xn, y1, ..., yn`, see `Tensor1D::dot`.
+    Dot,
+    /// Op defined outside of the crate, see `CustomOp`.
     Custom(String),
+    /// Entry `(row, col)` of the fused product of two `Tensor2D`s, with the hub as its only child.
+    MatMul {
+        row: usize,
+        col: usize,
+    },
+    /// Hub of the fused product of an `n x k` and a `k x m` `Tensor2D`, with the entries
+    /// of both operands and then the `MatMulGrad` nodes as children. Only passes grads on,
+    /// its `data` is always zero.
+    MatMulHub {
+        n: usize,
+        k: usize,
+        m: usize,
+    },
+    /// Holds the grad of entry `(row, col)` of a fused product for the backward of the hub.
+    /// Has no children and its `data` is always zero.
+    MatMulGrad {
+        row: usize,
+        col: usize,
+    },
     None,
 }
 
-#[derive(Default)]
-pub struct VariableData {
-    pub data: f64,
-    pub grad: f64,
-    pub id: u16,
-    pub fun: Option<fn(&VariableData)>,
+impl Operation {
+    /// Recomputes the forward pass of the op from the values of its children.
+    ///
+    /// Returns `None` for ops whose forward isn't known to the crate
+    /// and for the nodes of matrix products.
+    pub fn eval<T: Scalar>(&self, inputs: &[T]) -> Option<T> {
+        let x = inputs.first().copied().unwrap_or(T::zero());
+        Some(match self {
+            Operation::ADD => x + inputs[1],
+            Operation::MUL => x * inputs[1],
+            Operation::DIV => x / inputs[1],
+            Operation::Pow(p) => x.powf(T::from_f64(*p)),
+            Operation::Sin => x.sin(),
+            Operation::Cos => x.cos(),
+            Operation::Exp => x.exp(),
+            Operation::Ln => x.ln(),
+            Operation::Tan => x.sin() / x.cos(),
+            Operation::Relu => max(x, T::zero()),
+            Operation::Sum => inputs.iter().copied().sum(),
+            Operation::Mean => mean(inputs),
+            Operation::Max => inputs.iter().copied().fold(x, max),
+            Operation::Min => inputs.iter().copied().fold(x, min),
+            Operation::Var => var(inputs),
+            Operation::Std => var(inputs).sqrt(),
+            Operation::LogSumExp => logsumexp(inputs),
+            Operation::Softmax => (x - inputs[1]).exp(),
+            Operation::LogSoftmax => x - inputs[1],
+            Operation::Dot => {
+                let (x, y) = inputs.split_at(inputs.len() / 2);
+                x.iter().zip(y).map(|(&a, &b)| a * b).sum()
+            }
+            Operation::Custom(_)
+            | Operation::MatMul { .. }
+            | Operation::MatMulHub { .. }
+            | Operation::MatMulGrad { .. }
+            | Operation::None => return None,
+        })
+    }
+}
+
+/// Propagates `grad` of a node to its children. Can capture state of the op.
+pub type BackwardFn<T = f64> = Box<dyn Fn(&VariableData<T>)>;
+
+pub struct VariableData<T: Scalar = f64> {
+    pub data: T,
+    pub grad: T,
+    pub id: usize,
+    pub fun: Option<BackwardFn<T>>,
     pub op: Option<Operation>,
-    pub children: Vec<Variable>,
+    pub children: Vec<Variable<T>>,
+}
+
+impl<T: Scalar> Default for VariableData<T> {
+    /// Each default node gets a fresh id, so `Variable::default()` is a distinct graph node.
+    fn default() -> Self {
+        VariableData {
+            data: T::zero(),
+            grad: T::zero(),
+            id: next_id(),
+            fun: None,
+            op: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl<T: Scalar> Drop for VariableData<T> {
+    /// Tears the graph down iteratively.
+    ///
+    /// The derived drop would free `children` recursively and overflow the stack
+    /// on long chains. Instead, children owned only by this node are moved onto a
+    /// local stack and their own children are taken before they are freed.
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.children);
+        while let Some(child) = stack.pop() {
+            if Rc::strong_count(&child) == 1 {
+                stack.append(&mut child.borrow_mut().children);
+            }
+        }
+    }
 }
 
-impl Deref for Variable {
-    type Target = Rc<RefCell<VariableData>>;
+impl<T: Scalar> Deref for Variable<T> {
+    type Target = Rc<RefCell<VariableData<T>>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl PartialEq for Variable {
+impl<T: Scalar> PartialEq for Variable<T> {
     fn eq(&self, other: &Self) -> bool {
         self.borrow().id == other.borrow().id
     }
 }
 
-impl Eq for Variable {}
+impl<T: Scalar> Eq for Variable<T> {}
 
-impl Hash for Variable {
+impl<T: Scalar> Hash for Variable<T> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.borrow().id.hash(state)
     }
 }
 
-// Operations for variables. auto_ops is amazing!
-impl_op_ex!(+ |a: &Variable, b: &Variable| -> Variable {
-    let out = Variable::from(a.borrow().data + b.borrow().data);
-    out.borrow_mut().op = Some(Operation::ADD);
-    out.borrow_mut().children = vec![Variable(Rc::clone(a)), Variable(Rc::clone(b))];
-    out.borrow_mut().fun = Some(|x: &VariableData| {
-        x.children[0].borrow_mut().grad += x.grad;
-        x.children[1].borrow_mut().grad += x.grad;
-    });
-    out.borrow_mut().id = random();
-    out
-});
-
-impl_op_ex_commutative!(+|a: f64, b: &Variable| -> Variable { b + Variable::from(a) });
-
-impl_op_ex!(*|a: &Variable, b: &Variable| -> Variable {
-    let out = Variable::from(a.borrow().data * b.borrow().data);
-    out.borrow_mut().op = Some(Operation::MUL);
-    out.borrow_mut().children = vec![Variable(Rc::clone(a)), Variable(Rc::clone(b))];
-    out.borrow_mut().fun = Some(|x: &VariableData| {
-        let a = x.children[0].borrow().data;
-        let b = x.children[1].borrow().data;
-        x.children[0].borrow_mut().grad += b * x.grad;
-        x.children[1].borrow_mut().grad += a * x.grad;
-    });
-    out
-});
-impl_op_ex_commutative!(*|a: &Variable, b: f64| -> Variable { a * Variable::from(b) });
-
-impl_op_ex!(/|a: &Variable, b: &Variable| -> Variable {
-    assert!(b.data() != 0.0, "dividing by zero");
-    let out = Variable::from(a.borrow().data / b.borrow().data);
-    out.borrow_mut().op = Some(Operation::DIV);
-    out.borrow_mut().children = vec![Variable(Rc::clone(a)), Variable(Rc::clone(b))];
-    out.borrow_mut().fun = Some(|x: &VariableData| {
-        let a = x.children[0].borrow().data;
-        let b = x.children[1].borrow().data;
-        assert!(b != 0.0, "dividing by zero inside backprop");
-        x.children[0].borrow_mut().grad += x.grad / b;
-        x.children[1].borrow_mut().grad += a * -x.grad / (b.powf(2.));
-    });
-    out
-});
-
-impl_op_ex!(/|a: &Variable, b: f64| -> Variable { a / Variable::from(b) });
-impl_op_ex!(/|a: f64, b: &Variable| -> Variable { Variable::from(a) / b });
-
-impl_op!(-|a: &Variable| -> Variable { a * -1.0 });
-impl_op!(-|a: Variable| -> Variable { a * -1.0 });
-
-impl_op_ex!(-|a: &Variable, b: &Variable| -> Variable { a + -b });
-impl_op_ex!(-|a: &Variable, b: f64| -> Variable { a - Variable::from(b) });
-impl_op_ex!(-|a: f64, b: &Variable| -> Variable { Variable::from(a) - b });
+// Operations for variables. `auto_ops` can't do generics, see `forward_binop!`.
+impl<T: Scalar> Add<&Variable<T>> for &Variable<T> {
+    type Output = Variable<T>;
+
+    fn add(self, b: &Variable<T>) -> Variable<T> {
+        Variable::from_op(
+            self.borrow().data + b.borrow().data,
+            Operation::ADD,
+            || vec![Variable(Rc::clone(self)), Variable(Rc::clone(b))],
+            |x: &VariableData<T>| {
+                x.children[0].borrow_mut().grad += x.grad;
+                x.children[1].borrow_mut().grad += x.grad;
+            },
+        )
+    }
+}
+
+impl<T: Scalar> Add<T> for &Variable<T> {
+    type Output = Variable<T>;
+
+    fn add(self, b: T) -> Variable<T> {
+        self + Variable::from_value(b)
+    }
+}
+
+impl<T: Scalar> Mul<&Variable<T>> for &Variable<T> {
+    type Output = Variable<T>;
+
+    // `+=` of the backward fn accumulates grads
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, b: &Variable<T>) -> Variable<T> {
+        Variable::from_op(
+            self.borrow().data * b.borrow().data,
+            Operation::MUL,
+            || vec![Variable(Rc::clone(self)), Variable(Rc::clone(b))],
+            |x: &VariableData<T>| {
+                let a = x.children[0].borrow().data;
+                let b = x.children[1].borrow().data;
+                x.children[0].borrow_mut().grad += b * x.grad;
+                x.children[1].borrow_mut().grad += a * x.grad;
+            },
+        )
+    }
+}
+
+impl<T: Scalar> Mul<T> for &Variable<T> {
+    type Output = Variable<T>;
+
+    fn mul(self, b: T) -> Variable<T> {
+        self * Variable::from_value(b)
+    }
+}
+
+impl<T: Scalar> Div<&Variable<T>> for &Variable<T> {
+    type Output = Variable<T>;
+
+    fn div(self, b: &Variable<T>) -> Variable<T> {
+        assert!(b.data() != T::zero(), "dividing by zero");
+        Variable::from_op(
+            self.borrow().data / b.borrow().data,
+            Operation::DIV,
+            || vec![Variable(Rc::clone(self)), Variable(Rc::clone(b))],
+            |x: &VariableData<T>| {
+                let a = x.children[0].borrow().data;
+                let b = x.children[1].borrow().data;
+                assert!(b != T::zero(), "dividing by zero inside backprop");
+                x.children[0].borrow_mut().grad += x.grad / b;
+                x.children[1].borrow_mut().grad += a * -x.grad / (b * b);
+            },
+        )
+    }
+}
+
+impl<T: Scalar> Div<T> for &Variable<T> {
+    type Output = Variable<T>;
+
+    fn div(self, b: T) -> Variable<T> {
+        self / Variable::from_value(b)
+    }
+}
+
+impl<T: Scalar> Neg for &Variable<T> {
+    type Output = Variable<T>;
+
+    fn neg(self) -> Variable<T> {
+        self * -T::one()
+    }
+}
+
+impl<T: Scalar> Sub<&Variable<T>> for &Variable<T> {
+    type Output = Variable<T>;
+
+    fn sub(self, b: &Variable<T>) -> Variable<T> {
+        self + -b
+    }
+}
+
+impl<T: Scalar> Sub<T> for &Variable<T> {
+    type Output = Variable<T>;
+
+    fn sub(self, b: T) -> Variable<T> {
+        self - Variable::from_value(b)
+    }
+}
+
+forward_binop!(impl Add, add for Variable<T>, Variable<T> => Variable<T>);
+forward_binop!(impl Mul, mul for Variable<T>, Variable<T> => Variable<T>);
+forward_binop!(impl Div, div for Variable<T>, Variable<T> => Variable<T>);
+forward_binop!(impl Sub, sub for Variable<T>, Variable<T> => Variable<T>);
+forward_binop_scalar!(impl Add, add for Variable<T> => Variable<T>);
+forward_binop_scalar!(impl Mul, mul for Variable<T> => Variable<T>);
+forward_binop_scalar!(impl Div, div for Variable<T> => Variable<T>);
+forward_binop_scalar!(impl Sub, sub for Variable<T> => Variable<T>);
+forward_unop!(impl Neg, neg for Variable<T>);
+
+/// Ops with a plain number on the left, e.g. `1.0 - &x`.
+///
+/// Can't be generic over `T`, because the foreign type `T` would be `Self` of the impl.
+macro_rules! impl_scalar_lhs {
+    ($($t:ty),*) => {$(
+        impl_scalar_lhs!(@op $t, Add, add, |a, b| b + a);
+        impl_scalar_lhs!(@op $t, Mul, mul, |a, b| b * a);
+        impl_scalar_lhs!(@op $t, Sub, sub, |a, b| Variable::from_value(a) - b);
+        impl_scalar_lhs!(@op $t, Div, div, |a, b| Variable::from_value(a) / b);
+    )*};
+    (@op $t:ty, $imp:ident, $method:ident, |$a:ident, $b:ident| $body:expr) => {
+        impl $imp<&Variable<$t>> for $t {
+            type Output = Variable<$t>;
+
+            fn $method(self, $b: &Variable<$t>) -> Variable<$t> {
+                let $a = self;
+                $body
+            }
+        }
+
+        impl $imp<Variable<$t>> for $t {
+            type Output = Variable<$t>;
+
+            fn $method(self, $b: Variable<$t>) -> Variable<$t> {
+                self.$method(&$b)
+            }
+        }
+    };
+}
+
+impl_scalar_lhs!(f32, f64);
 
 impl Variable {
-    pub fn new(tensor: VariableData) -> Variable {
+    pub fn from<T: Into<f64>>(f: T) -> Variable {
+        Variable::from_value(f.into())
+    }
+}
+
+impl<T: Scalar> Variable<T> {
+    pub fn new(tensor: VariableData<T>) -> Variable<T> {
         Variable(Rc::new(RefCell::new(tensor)))
     }
 
-    pub fn grad(&self) -> f64 {
+    pub fn grad(&self) -> T {
         self.borrow().grad
     }
 
-    pub fn data(&self) -> f64 {
+    pub fn data(&self) -> T {
         self.borrow().data
     }
 
-    pub fn from<T: Into<f64>>(f: T) -> Variable {
+    /// Leaf holding `x`. Same as `Variable::from`, but for any precision.
+    pub fn from_value(x: T) -> Variable<T> {
         let out = Variable::default();
-        out.borrow_mut().data = f.into();
-        out.borrow_mut().id = random();
+        out.borrow_mut().data = x;
         out
     }
 
+    /// Creates the result node of an op.
+    ///
+    /// Inside of `no_grad` only `data` is kept: `children` isn't even called,
+    /// so no inputs are cloned and no backward fn is recorded.
+    pub(crate) fn from_op(
+        data: T,
+        op: Operation,
+        children: impl FnOnce() -> Vec<Variable<T>>,
+        fun: impl Fn(&VariableData<T>) + 'static,
+    ) -> Variable<T> {
+        let out = Variable::from_value(data);
+        if is_grad_enabled() {
+            let mut inner = out.borrow_mut();
+            inner.op = Some(op);
+            inner.children = children();
+            inner.fun = Some(Box::new(fun));
+        }
+        out
+    }
+
+    /// Backpropagates from `self`, accumulating into the grads of the leaves.
     pub fn backward(&mut self) {
-        self.borrow_mut().grad = 1.0;
-        let (mut order, mut used) = (Vec::new(), HashSet::new());
-        self.dfs(&mut order, &mut used);
+        self.backward_with(true);
+    }
+
+    /// Backpropagates from `self`.
+    ///
+    /// Grads of intermediate nodes are always recomputed from scratch, so calling it
+    /// twice on the same graph doesn't double-count. If `accumulate` is false, grads of
+    /// the leaves are reset as well, otherwise new grads are added to the old ones.
+    pub fn backward_with(&mut self, accumulate: bool) {
+        let order = self.topological_order();
+        order.iter().for_each(|it| {
+            let mut node = it.borrow_mut();
+            if node.fun.is_some() || !accumulate {
+                node.grad = T::zero();
+            }
+        });
+        self.borrow_mut().grad = T::one();
         order.into_iter().rev().for_each(|it| {
-            if let Some(fun) = it.borrow().fun {
+            if let Some(fun) = &it.borrow().fun {
                 fun(&it.borrow());
             }
         });
     }
 
-    fn dfs(&self, order: &mut Vec<Variable>, used: &mut HashSet<Variable>) {
-        if used.insert(self.clone()) {
-            self.borrow().children.iter().for_each(|child| {
-                child.dfs(order, used);
+    /// Returns every node reachable from `self`, children before parents.
+    ///
+    /// Uses an explicit stack instead of recursion, so the depth of the graph
+    /// is not limited by the size of the call stack.
+    pub(crate) fn topological_order(&self) -> Vec<Variable<T>> {
+        let (mut order, mut used) = (Vec::new(), HashSet::new());
+        // (node, children already pushed)
+        let mut stack = vec![(self.clone(), false)];
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                order.push(node);
+                continue;
+            }
+            if !used.insert(node.borrow().id) {
+                continue;
+            }
+            stack.push((node.clone(), true));
+            node.borrow().children.iter().for_each(|child| {
+                if !used.contains(&child.borrow().id) {
+                    stack.push((child.clone(), false));
+                }
             });
-            order.push(self.clone());
         }
+        order
     }
 
-    pub fn pow(&self, p: f64) -> Variable {
-        let out = Variable::from(self.borrow().data.powf(p));
-        out.borrow_mut().op = Some(Operation::Custom(String::from("pow")));
-        out.borrow_mut().children = vec![self.clone(), Variable::from(p)];
-        out.borrow_mut().fun = Some(|x: &VariableData| {
-            let pow = x.children[1].borrow().data;
-            let a = x.children[0].borrow().data.powf(pow - 1.0) * pow;
-            x.children[0].borrow_mut().grad += x.grad * a;
-        });
-        out.borrow_mut().id = random();
-        out
+    pub fn pow(&self, p: T) -> Variable<T> {
+        Variable::from_op(
+            self.borrow().data.powf(p),
+            Operation::Pow(p.to_f64()),
+            || vec![self.clone()],
+            move |x: &VariableData<T>| {
+                let a = x.children[0].borrow().data.powf(p - T::one()) * p;
+                x.children[0].borrow_mut().grad += x.grad * a;
+            },
+        )
     }
 
-    pub fn sin(&self) -> Variable {
-        let out = Variable::from(unsafe { sinf64(self.borrow().data) });
-        out.borrow_mut().op = Some(Operation::Custom(String::from("sin")));
-        out.borrow_mut().children = vec![self.clone()];
-        out.borrow_mut().fun = Some(|x: &VariableData| {
-            let val = x.children[0].borrow().data;
-            x.children[0].borrow_mut().grad += x.grad * unsafe { cosf64(val) };
-        });
-        out
+    pub fn sin(&self) -> Variable<T> {
+        Variable::from_op(
+            self.borrow().data.sin(),
+            Operation::Sin,
+            || vec![self.clone()],
+            |x: &VariableData<T>| {
+                let val = x.children[0].borrow().data;
+                x.children[0].borrow_mut().grad += x.grad * val.cos();
+            },
+        )
     }
 
-    pub fn cos(&self) -> Variable {
-        let out = Variable::from(unsafe { cosf64(self.borrow().data) });
-        out.borrow_mut().op = Some(Operation::Custom(String::from("cos")));
-        out.borrow_mut().children = vec![self.clone()];
-        out.borrow_mut().fun = Some(|x: &VariableData| {
-            let val = x.children[0].borrow().data;
-            x.children[0].borrow_mut().grad += -x.grad * unsafe { sinf64(val) };
-        });
-        out
+    pub fn cos(&self) -> Variable<T> {
+        Variable::from_op(
+            self.borrow().data.cos(),
+            Operation::Cos,
+            || vec![self.clone()],
+            |x: &VariableData<T>| {
+                let val = x.children[0].borrow().data;
+                x.children[0].borrow_mut().grad += -x.grad * val.sin();
+            },
+        )
     }
 
-    pub fn relu(&self) -> Variable {
-        let out = Variable::from(max(self.borrow().data, 0.0));
-        out.borrow_mut().op = Some(Operation::Custom(String::from("relu")));
-        out.borrow_mut().children = vec![self.clone()];
-        out.borrow_mut().fun = Some(|x: &VariableData| {
-            if x.children[0].borrow().data > 0.0 {
-                x.children[0].borrow_mut().grad += x.grad;
-            }
-        });
-        out
+    pub fn relu(&self) -> Variable<T> {
+        Variable::from_op(
+            max(self.borrow().data, T::zero()),
+            Operation::Relu,
+            || vec![self.clone()],
+            |x: &VariableData<T>| {
+                if x.children[0].borrow().data > T::zero() {
+                    x.children[0].borrow_mut().grad += x.grad;
+                }
+            },
+        )
     }
 
-    pub fn exp(&self) -> Variable {
-        let exp = unsafe { expf64(self.borrow().data) };
-        let out = Variable::from(exp);
-        out.borrow_mut().op = Some(Operation::Custom(String::from("exp")));
-        out.borrow_mut().children = vec![self.clone()];
-        out.borrow_mut().fun = Some(|x: &VariableData| {
-            let val = unsafe { expf64(x.children[0].borrow_mut().data) };
-            x.children[0].borrow_mut().grad += x.grad * val;
-        });
-        out
+    pub fn exp(&self) -> Variable<T> {
+        let exp = self.borrow().data.exp();
+        Variable::from_op(
+            exp,
+            Operation::Exp,
+            || vec![self.clone()],
+            |x: &VariableData<T>| {
+                let val = x.children[0].borrow().data.exp();
+                x.children[0].borrow_mut().grad += x.grad * val;
+            },
+        )
     }
 
-    pub fn ln(&self) -> Variable {
-        let ln = unsafe { logf64(self.borrow().data) };
-        let out = Variable::from(ln);
-        out.borrow_mut().op = Some(Operation::Custom(String::from("ln")));
-        out.borrow_mut().children = vec![self.clone()];
-        out.borrow_mut().fun = Some(|x: &VariableData| {
-            let val = (x.children[0].borrow_mut().data);
-            x.children[0].borrow_mut().grad += -x.grad / val.powf(2.0);
-        });
-        out
+    pub fn ln(&self) -> Variable<T> {
+        let ln = self.borrow().data.ln();
+        Variable::from_op(
+            ln,
+            Operation::Ln,
+            || vec![self.clone()],
+            |x: &VariableData<T>| {
+                let val = x.children[0].borrow().data;
+                x.children[0].borrow_mut().grad += x.grad / val;
+            },
+        )
     }
 
-    pub fn tan(&self) -> Variable {
-        let tan = unsafe { sinf64(self.borrow().data) / cosf64(self.borrow().data) };
-        let out = Variable::from(tan);
-        out.borrow_mut().op = Some(Operation::Custom(String::from("tan")));
-        out.borrow_mut().children = vec![self.clone()];
-        out.borrow_mut().fun = Some(|x: &VariableData| {
-            let val = unsafe { cosf64(x.children[0].borrow_mut().data) };
-            x.children[0].borrow_mut().grad += x.grad / val.powf(2.0);
-        });
-        out
+    pub fn tan(&self) -> Variable<T> {
+        let tan = self.borrow().data.sin() / self.borrow().data.cos();
+        Variable::from_op(
+            tan,
+            Operation::Tan,
+            || vec![self.clone()],
+            |x: &VariableData<T>| {
+                let val = x.children[0].borrow().data.cos();
+                x.children[0].borrow_mut().grad += x.grad / (val * val);
+            },
+        )
     }
 
-    pub fn silu(&self) -> Variable {
+    pub fn silu(&self) -> Variable<T> {
         self * self.sigmoid()
     }
 
-    pub fn sigmoid(&self) -> Variable {
-        (Variable::from(1.0) + (-self).exp()).pow(-1.0)
+    pub fn sigmoid(&self) -> Variable<T> {
+        (Variable::from_value(T::one()) + (-self).exp()).pow(-T::one())
     }
 
     /// for gradient descent
     pub fn zero_grad(&self) {
         assert!(self.borrow().children.is_empty());
-        self.borrow_mut().grad = 0.0;
+        self.borrow_mut().grad = T::zero();
+    }
+
+    /// Resets grads of every node reachable from `self`, leaves included.
+    pub fn zero_grad_graph(&self) {
+        self.topological_order()
+            .iter()
+            .for_each(|it| it.borrow_mut().grad = T::zero());
     }
 
-    pub fn step(&self, lr: f64) {
+    pub fn step(&self, lr: T) {
         let grad = self.grad();
         self.borrow_mut().data -= lr * grad;
     }
@@ -0,0 +1,105 @@
+#[macro_use]
+mod common;
+use common::{grads, values};
+use rust_minigrad::{Tensor1D, Tensor2D, Variable};
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn bias_row() {
+        let batch = Tensor2D::from(&vec![vec![1., 2., 3.], vec![4., 5., 6.]]);
+        let bias = Tensor1D::from(&vec![0.5, -1., 2.]);
+        let out = &batch + &bias;
+        assert_eq!(out.shape(), (2, 3));
+        assert_eq!(values(&out), vec![vec![1.5, 1., 5.], vec![4.5, 4., 8.]]);
+
+        // every bias entry is used by both rows
+        let mut loss = (&out * 2.0).sum();
+        loss.backward();
+        assert_eq!(grads(&bias), vec![4., 4., 4.]);
+        assert_eq!(batch.borrow()[1][2].grad(), 2.);
+    }
+
+    #[test]
+    fn column_and_row() {
+        let col = Tensor2D::from(&vec![vec![1.], vec![2.]]);
+        let row = Tensor2D::from(&vec![vec![10., 20., 30.]]);
+        let out = &col - &row;
+        assert_eq!(
+            values(&out),
+            vec![vec![-9., -19., -29.], vec![-8., -18., -28.]]
+        );
+        let mut s = out.sum();
+        s.backward();
+        assert_eq!(col.borrow()[0][0].grad(), 3.);
+        assert_eq!(row.borrow()[0][1].grad(), -2.);
+    }
+
+    #[test]
+    fn tensor1d_elementwise() {
+        let x = Tensor1D::from(&vec![1., 2., 4.]);
+        let y = Tensor1D::from(&vec![2., 4., 8.]);
+        let prod = &x * &y;
+        let quot = &y / &x;
+        assert_eq!(prod.shape(), (3,));
+        assert_eq!(prod.borrow()[2].data(), 32.);
+        assert_eq!(quot.borrow()[1].data(), 2.);
+
+        let one = Tensor1D::from(&vec![3.]);
+        assert_eq!((&x + &one).borrow()[2].data(), 7.);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't broadcast")]
+    fn tensor1d_length_mismatch() {
+        let _ = Tensor1D::from(&vec![1., 2., 3.]) + Tensor1D::from(&vec![1., 2.]);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't broadcast")]
+    fn tensor2d_shape_mismatch() {
+        let a = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        let b = Tensor2D::from(&vec![vec![1., 2., 3.]]);
+        let _ = &a + &b;
+    }
+
+    #[test]
+    fn scalars() {
+        let x = Tensor1D::from(&vec![1., 2.]);
+        let m = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        assert_eq!((&x * 3.0).borrow()[1].data(), 6.);
+        assert_eq!((1.0 - &x).borrow()[1].data(), -1.);
+        assert_eq!((&m / 2.0).borrow()[1][0].data(), 1.5);
+        assert_eq!(values(&(12.0 / &m)), vec![vec![12., 6.], vec![4., 3.]]);
+        let s = Variable::from(2.0);
+        assert_eq!((&x - &s).borrow()[0].data(), -1.);
+        assert_eq!(values(&(&s / &m))[0][1], 1.);
+    }
+
+    #[test]
+    fn broadcast_grads() {
+        let m = Tensor2D::from(&vec![vec![0.3, -0.8, 1.2], vec![0.5, 0.9, -0.1]]);
+        let v = Tensor1D::from(&vec![0.7, 1.3, -0.4]);
+        let mv = (&m, &v);
+        check!(mv, |(m, v): &(&Tensor2D, &Tensor1D)| ((*m + *v)
+            / (*v * *v + 1.0))
+            .sum());
+        check!(mv, |(m, v): &(&Tensor2D, &Tensor1D)| ((*v - *m)
+            / (*m - 2.0))
+            .sum());
+        check!(mv, |(m, v): &(&Tensor2D, &Tensor1D)| (*m / *v).sum());
+        check!(mv, |(m, v): &(&Tensor2D, &Tensor1D)| (1.0
+            / (*v / *m + 3.0))
+            .sum());
+        check!(mv, |(_, v): &(&Tensor2D, &Tensor1D)| (*v * *v - 1.0).sum());
+        check!(mv, |(m, v): &(&Tensor2D, &Tensor1D)| (*m + (*v * 2.0))
+            .mean());
+        // the row-broadcast product, `Tensor1D * Tensor2D` isn't one
+        check!(mv, |(m, v): &(&Tensor2D, &Tensor1D)| m
+            .hadamard(&v.t())
+            .sin()
+            .sum());
+    }
+}
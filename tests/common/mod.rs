@@ -0,0 +1,66 @@
+//! Helpers shared by the integration tests, included with `#[macro_use] mod common;`.
+#![allow(dead_code, unused_macros)]
+
+use rust_minigrad::{Tensor1D, Tensor2D, Variable};
+
+/// Step and tolerance of `check!`.
+pub const EPS: f64 = 1e-6;
+pub const TOL: f64 = 1e-5;
+
+macro_rules! assert_close {
+    ($left:expr, $right:expr, $tol:expr) => {{
+        let (left, right, tol) = (&$left, &$right, &$tol);
+        assert!(
+            (*left - *right).abs() < *tol,
+            "assertion failed: `(left ~ right)`\n  left: `{}`,\n right: `{}`\n  diff: `{}`",
+            *left,
+            *right,
+            (*left - *right).abs()
+        );
+    }};
+}
+
+/// Runs `gradcheck` of `f` at `inputs`, panicking with the expression of `f` on failure.
+macro_rules! check {
+    ($inputs:expr, $f:expr) => {
+        if let Err(err) =
+            rust_minigrad::gradcheck($f, &$inputs, $crate::common::EPS, $crate::common::TOL)
+        {
+            panic!("{}: {}", stringify!($f), err);
+        }
+    };
+}
+
+/// Tensor whose elements can be read back as plain numbers, keeping its shape.
+pub trait Elements {
+    type Out;
+
+    fn map_elements(&self, f: impl Fn(&Variable) -> f64) -> Self::Out;
+}
+
+impl Elements for Tensor1D {
+    type Out = Vec<f64>;
+
+    fn map_elements(&self, f: impl Fn(&Variable) -> f64) -> Vec<f64> {
+        self.borrow().iter().map(f).collect()
+    }
+}
+
+impl Elements for Tensor2D {
+    type Out = Vec<Vec<f64>>;
+
+    fn map_elements(&self, f: impl Fn(&Variable) -> f64) -> Vec<Vec<f64>> {
+        self.borrow()
+            .iter()
+            .map(|row| row.iter().map(&f).collect())
+            .collect()
+    }
+}
+
+pub fn values<E: Elements>(x: &E) -> E::Out {
+    x.map_elements(|v| v.data())
+}
+
+pub fn grads<E: Elements>(x: &E) -> E::Out {
+    x.map_elements(|v| v.grad())
+}
@@ -0,0 +1,74 @@
+#[macro_use]
+mod common;
+use rust_minigrad::{CustomOp, Operation, Variable};
+
+/// (x, y) -> sqrt(x^2 + y^2)
+struct Hypot;
+
+impl CustomOp for Hypot {
+    fn name(&self) -> &str {
+        "hypot"
+    }
+
+    fn forward(&self, inputs: &[f64]) -> f64 {
+        inputs[0].hypot(inputs[1])
+    }
+
+    fn backward(&self, inputs: &[f64], output: f64, grad: f64) -> Vec<f64> {
+        vec![grad * inputs[0] / output, grad * inputs[1] / output]
+    }
+}
+
+/// Piecewise-linear activation given by a lookup table on [0, 1, 2, ...].
+struct Table(Vec<f64>);
+
+impl CustomOp for Table {
+    fn name(&self) -> &str {
+        "table"
+    }
+
+    fn forward(&self, inputs: &[f64]) -> f64 {
+        let i = inputs[0].floor() as usize;
+        let t = inputs[0] - i as f64;
+        self.0[i] + t * (self.0[i + 1] - self.0[i])
+    }
+
+    fn backward(&self, inputs: &[f64], _output: f64, grad: f64) -> Vec<f64> {
+        let i = inputs[0].floor() as usize;
+        vec![grad * (self.0[i + 1] - self.0[i])]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn hypot() {
+        let x = Variable::from(3.0);
+        let y = Variable::from(4.0);
+        let mut z = Variable::custom(Hypot, &[x.clone(), y.clone()]);
+        assert_close!(z.data(), 5.0, 0.0001);
+        z.backward();
+        assert_close!(x.grad(), 0.6, 0.0001);
+        assert_close!(y.grad(), 0.8, 0.0001);
+        assert!(matches!(&z.borrow().op, Some(Operation::Custom(name)) if name == "hypot"));
+    }
+
+    #[test]
+    fn stateful_op() {
+        let x = Variable::from(1.5);
+        let table = Table(vec![0.0, 1.0, 3.0, 7.0]);
+        let mut y = Variable::custom(table, &[&x * &x]).sin();
+        y.backward();
+        // table(2.25) = 4, slope 4 => d/dx sin(table(x^2)) = cos(4) * 4 * 2x
+        assert_close!(x.grad(), 4.0_f64.cos() * 4.0 * 3.0, 0.0001);
+    }
+
+    #[test]
+    fn pow_has_no_fake_child() {
+        let x = Variable::from(2.0);
+        let y = x.pow(3.0);
+        assert_eq!(y.borrow().children.len(), 1);
+    }
+}
@@ -0,0 +1,83 @@
+#[macro_use]
+mod common;
+use common::{grads, values};
+use rust_minigrad::{grad, Operation, Tensor1D, Variable};
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn products() {
+        let x = Tensor1D::from(&vec![1., 2., 3.]);
+        let y = Tensor1D::from(&vec![4., -5., 6.]);
+        assert_eq!(values(&x.hadamard(&y)), vec![4., -10., 18.]);
+        assert_eq!(x.dot(&y).data(), 12.);
+        let o = x.outer(&Tensor1D::from(&vec![1., -1.]));
+        assert_eq!(o.shape(), (3, 2));
+        assert_eq!(o.borrow()[2][1].data(), -3.);
+        assert!(o.borrow()[2][1].borrow().children[0] == x.borrow()[2]);
+    }
+
+    #[test]
+    fn dot_is_single_node() {
+        let x = Tensor1D::from(&vec![1., 2., 3.]);
+        let y = Tensor1D::from(&vec![4., -5., 6.]);
+        let mut d = x.dot(&y);
+        assert_eq!(d.borrow().op, Some(Operation::Dot));
+        assert_eq!(d.borrow().children.len(), 6);
+        d.backward();
+        assert_eq!(grads(&x), vec![4., -5., 6.]);
+        assert_eq!(grads(&y), vec![1., 2., 3.]);
+
+        // both operands are the same tensor
+        let z = Tensor1D::from(&vec![1., 2., 3.]);
+        let mut d = z.dot(&z);
+        d.backward();
+        assert_eq!(grads(&z), vec![2., 4., 6.]);
+    }
+
+    #[test]
+    #[should_panic(expected = "1D shapes must be equal")]
+    fn dot_length_mismatch() {
+        Tensor1D::from(&vec![1., 2.]).dot(&Tensor1D::from(&vec![1., 2., 3.]));
+    }
+
+    #[test]
+    fn product_grads() {
+        let x = Tensor1D::from(&vec![0.3, -0.8, 1.2]);
+        let y = Tensor1D::from(&vec![0.5, 0.9, -0.1]);
+        let p = Tensor1D::from(&vec![0.2, 0.5, 0.3]);
+        check!((&x, &y), |(x, y): &(&Tensor1D, &Tensor1D)| x.dot(y));
+        check!((&x, &y), |(x, y): &(&Tensor1D, &Tensor1D)| x
+            .hadamard(y)
+            .sin()
+            .sum());
+        check!((&x, &y), |(x, y): &(&Tensor1D, &Tensor1D)| x
+            .outer(y)
+            .exp()
+            .sum());
+        check!(x, |x: &Tensor1D| x.dot(&x.sin()));
+        check!((&p, &x), |(p, x): &(&Tensor1D, &Tensor1D)| p
+            .cross_entropy_loss(&x.exp()));
+    }
+
+    #[test]
+    fn cross_entropy() {
+        let p = Tensor1D::from(&vec![0.25, 0.75]);
+        let q = Tensor1D::from(&vec![0.5, 0.5]);
+        let loss: Variable = p.cross_entropy_loss(&q);
+        assert!((loss.data() - 2f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dot_create_graph() {
+        // d (x . x) / dx = 2x, d^2 / dx^2 = 2
+        let x = Tensor1D::from(&vec![1., -2.]);
+        let leaves = x.borrow().clone();
+        let g = grad(&x.dot(&x), &leaves);
+        assert_eq!((g[0].data(), g[1].data()), (2., -4.));
+        let h = grad(&g[1], &leaves);
+        assert_eq!((h[0].data(), h[1].data()), (0., 2.));
+    }
+}
@@ -0,0 +1,67 @@
+#[macro_use]
+mod common;
+use rust_minigrad::{jvp, Dual, Variable};
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    /// Same expression written for both modes, checked against each other.
+    macro_rules! cross_check {
+        ($x0:expr, |$x:ident| $body:expr) => {{
+            let $x = Dual::variable($x0);
+            let forward = $body;
+
+            let $x = Variable::from($x0);
+            let mut y = $body;
+            y.backward();
+
+            assert_close!(forward.data(), y.data(), 1e-12);
+            assert_close!(forward.tangent(), $x.grad(), 1e-9);
+        }};
+    }
+
+    // the same expressions are used for `Variable`, which needs references
+    #[allow(clippy::op_ref)]
+    #[test]
+    fn matches_reverse_mode() {
+        cross_check!(0.7, |x| &x + &x * 3.0 - 1.0);
+        cross_check!(0.7, |x| (&x * &x) / (&x + 2.0));
+        cross_check!(0.7, |x| 2.0 / &x - &x);
+        cross_check!(0.7, |x| -x.pow(3.0));
+        cross_check!(0.7, |x| x.sin() * x.cos());
+        cross_check!(0.7, |x| x.exp().tan());
+        cross_check!(0.7, |x| x.relu() + (-&x).relu());
+        cross_check!(0.7, |x| x.sigmoid());
+        cross_check!(-0.3, |x| x.silu());
+    }
+
+    #[test]
+    fn ln() {
+        let x = Dual::variable(2.0);
+        let y = (x * x).ln();
+        assert_close!(y.data(), 4.0_f64.ln(), 1e-12);
+        assert_close!(y.tangent(), 1.0, 1e-12);
+    }
+
+    #[test]
+    fn constants_have_no_tangent() {
+        let c = Dual::from(3.0);
+        assert_eq!((c * c).sin().tangent(), 0.0);
+    }
+
+    #[test]
+    fn jvp_column() {
+        // f(x, y) = (x y, sin x, y^2), J = [[y, x], [cos x, 0], [0, 2y]]
+        let f = |v: &[Dual]| vec![v[0] * v[1], v[0].sin(), v[1].pow(2.0)];
+        let (y, col) = jvp(f, &[1.0, 2.0], &[1.0, 0.0]);
+        assert_eq!(y, vec![2.0, 1.0_f64.sin(), 4.0]);
+        assert_close!(col[0], 2.0, 1e-12);
+        assert_close!(col[1], 1.0_f64.cos(), 1e-12);
+        assert_close!(col[2], 0.0, 1e-12);
+
+        let (_, jv) = jvp(f, &[1.0, 2.0], &[0.5, -1.0]);
+        assert_close!(jv[0], 0.5 * 2.0 - 1.0, 1e-12);
+        assert_close!(jv[2], -4.0, 1e-12);
+    }
+}
@@ -0,0 +1,113 @@
+#[macro_use]
+mod common;
+use common::values;
+use rust_minigrad::{hessian, jacobian, jvp, Dual, Tensor1D};
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn jacobian_small() {
+        // f(x) = (x0 x1, sin x0, x1^2 + x2)
+        let f = |x: &Tensor1D| {
+            let x = x.borrow();
+            let out = Tensor1D::new(3);
+            *out.borrow_mut() = vec![&x[0] * &x[1], x[0].sin(), x[1].pow(2.0) + &x[2]];
+            out
+        };
+        let x = Tensor1D::from(&vec![1.0, 2.0, 3.0]);
+        let j = jacobian(f, &x);
+        assert_eq!(j.shape(), (3, 3));
+
+        let expected = [[2.0, 1.0, 0.0], [1.0_f64.cos(), 0.0, 0.0], [0.0, 4.0, 1.0]];
+        for (row, expected) in j.borrow().iter().zip(expected.iter()) {
+            for (v, e) in row.iter().zip(expected.iter()) {
+                assert_close!(v.data(), e, 1e-12);
+            }
+        }
+        // grads of the caller's tensor are not touched
+        assert!(x.borrow().iter().all(|v| v.grad() == 0.0));
+    }
+
+    #[test]
+    fn jacobian_matches_jvp() {
+        let f = |x: &Tensor1D| (x.exp() * x.sum()).sin();
+        let x0 = vec![0.1, -0.4, 0.3];
+        let j = jacobian(f, &Tensor1D::from(&x0));
+
+        for col in 0..3 {
+            let v = (0..3).map(|i| (i == col) as i32 as f64).collect::<Vec<_>>();
+            let (_, jv) = jvp(
+                |x: &[Dual]| {
+                    let s = x[0] + x[1] + x[2];
+                    x.iter().map(|xi| (xi.exp() * s).sin()).collect()
+                },
+                &x0,
+                &v,
+            );
+            for (row, expected) in j.borrow().iter().zip(jv.iter()) {
+                assert_close!(row[col].data(), expected, 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn hessian_rosenbrock() {
+        // f = (1 - x)^2 + 100 (y - x^2)^2
+        let f = |v: &Tensor1D| {
+            let v = v.borrow();
+            (1.0 - &v[0]).pow(2.0) + (&v[1] - v[0].pow(2.0)).pow(2.0) * 100.0
+        };
+        let (x, y) = (0.5, 1.5);
+        let h = hessian(f, &Tensor1D::from(&vec![x, y]));
+        let expected = [
+            [2.0 - 400.0 * (y - x * x) + 800.0 * x * x, -400.0 * x],
+            [-400.0 * x, 200.0],
+        ];
+        for (row, expected) in h.borrow().iter().zip(expected.iter()) {
+            for (v, e) in row.iter().zip(expected.iter()) {
+                assert_close!(v.data(), e, 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn newton_step_on_quadratic() {
+        // f = x^2 + x y + 2 y^2 - x, the minimum is reached in one Newton step
+        let f = |v: &Tensor1D| {
+            let v = v.borrow();
+            v[0].pow(2.0) + &v[0] * &v[1] + v[1].pow(2.0) * 2.0 - &v[0]
+        };
+        let x = Tensor1D::from(&vec![3.0, -2.0]);
+        let h = hessian(f, &x);
+        let g = jacobian(
+            |v| {
+                let out = Tensor1D::new(1);
+                out.borrow_mut()[0] = f(v);
+                out
+            },
+            &x,
+        );
+
+        let h = h.borrow();
+        let (a, b, c, d) = (
+            h[0][0].data(),
+            h[0][1].data(),
+            h[1][0].data(),
+            h[1][1].data(),
+        );
+        let det = a * d - b * c;
+        let (g0, g1) = (g.borrow()[0][0].data(), g.borrow()[0][1].data());
+        let step = [(d * g0 - b * g1) / det, (a * g1 - c * g0) / det];
+        let x = values(&x);
+        assert_close!(x[0] - step[0], 4.0 / 7.0, 1e-9);
+        assert_close!(x[1] - step[1], -1.0 / 7.0, 1e-9);
+    }
+
+    #[test]
+    fn hessian_of_linear_function() {
+        let h = hessian(|v| v.sum() * 3.0, &Tensor1D::from(&vec![1.0, 2.0]));
+        assert!(h.borrow().iter().flatten().all(|v| v.data() == 0.0));
+    }
+}
@@ -0,0 +1,167 @@
+#[macro_use]
+mod common;
+use common::{EPS, TOL};
+use rust_minigrad::{gradcheck, CustomOp, Operation, Tensor1D, Tensor2D, Variable, VariableData};
+
+/// (x, y) -> x * exp(y)
+struct MulExp;
+
+impl CustomOp for MulExp {
+    fn name(&self) -> &str {
+        "mul_exp"
+    }
+
+    fn forward(&self, inputs: &[f64]) -> f64 {
+        inputs[0] * inputs[1].exp()
+    }
+
+    fn backward(&self, inputs: &[f64], output: f64, grad: f64) -> Vec<f64> {
+        vec![grad * inputs[1].exp(), grad * output]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn variable_ops() {
+        let x = Variable::from(0.7);
+        check!(x, |x: &Variable| x + x * 3.0 - 1.0);
+        check!(x, |x: &Variable| 2.0 - x);
+        check!(x, |x: &Variable| x / (x + 2.0));
+        check!(x, |x: &Variable| 2.0 / x);
+        check!(x, |x: &Variable| -x.pow(3.0));
+        check!(x, |x: &Variable| x.pow(-0.5));
+        check!(x, |x: &Variable| x.sin());
+        check!(x, |x: &Variable| x.cos());
+        check!(x, |x: &Variable| x.exp());
+        check!(x, |x: &Variable| x.ln());
+        check!(x, |x: &Variable| x.tan());
+        check!(x, |x: &Variable| x.relu() + (-x).relu());
+        check!(x, |x: &Variable| x.sigmoid());
+        check!(x, |x: &Variable| x.silu());
+    }
+
+    #[test]
+    fn binary_ops() {
+        let xy = (Variable::from(0.7), Variable::from(-1.3));
+        check!(xy, |(x, y): &(Variable, Variable)| x * y + x - y);
+        check!(xy, |(x, y): &(Variable, Variable)| x / y);
+        check!(xy, |(x, y): &(Variable, Variable)| Variable::custom(
+            MulExp,
+            &[x.clone(), y.clone()]
+        ));
+    }
+
+    #[test]
+    fn tensor1d_ops() {
+        let x = Tensor1D::from(&vec![0.3, -0.8, 1.2, 0.5]);
+        let y = Tensor1D::from(&vec![0.2, 0.1, 0.6, 0.1]);
+        check!(x, |x: &Tensor1D| x.sum());
+        check!(x, |x: &Tensor1D| x.mean());
+        check!(x, |x: &Tensor1D| x.sin().sum());
+        check!(x, |x: &Tensor1D| x.cos().sum());
+        check!(x, |x: &Tensor1D| x.exp().sum());
+        check!(x, |x: &Tensor1D| x.relu().sum());
+        check!(x, |x: &Tensor1D| x.silu().sum());
+        check!(x, |x: &Tensor1D| x.pow(3.0).sum());
+        check!(x, |x: &Tensor1D| x.exp().ln().sum());
+        check!(x, |x: &Tensor1D| (x.softmax() * x.sum()).sum());
+        check!(x, |x: &Tensor1D| x.l2_2());
+        check!(x, |x: &Tensor1D| x.l2_norm());
+        check!(x, |x: &Tensor1D| (-x + x.mean()).sum());
+        check!(x, |x: &Tensor1D| (x / x.sum()).sum());
+        check!(x, |x: &Tensor1D| x.t().sum());
+        check!((&x, &y), |(x, y): &(&Tensor1D, &Tensor1D)| x.mse_loss(y));
+        check!((&x, &y), |(x, y): &(&Tensor1D, &Tensor1D)| (*x - *y).sum());
+        check!((&x, &y), |(x, y): &(&Tensor1D, &Tensor1D)| x.dot(y));
+        check!((&x, &y), |(x, y): &(&Tensor1D, &Tensor1D)| y
+            .cross_entropy_loss(&x.softmax()));
+    }
+
+    #[test]
+    fn tensor2d_ops() {
+        let a = Tensor2D::from(&vec![vec![0.3, -0.8, 1.2], vec![0.5, 0.9, -0.1]]);
+        let b = Tensor2D::from(&vec![vec![0.2, 0.1], vec![-0.6, 0.4], vec![1.1, -0.7]]);
+        let x = Tensor1D::from(&vec![0.4, -0.2, 0.9]);
+        let s = Variable::from(1.7);
+        check!(a, |a: &Tensor2D| a.sum());
+        check!(a, |a: &Tensor2D| a.mean());
+        check!((&a, &b), |(a, b): &(&Tensor2D, &Tensor2D)| (*a * *b).sum());
+        check!((&a, &x), |(a, x): &(&Tensor2D, &Tensor1D)| (*a * *x).sum());
+        check!((&a, &s), |(a, s): &(&Tensor2D, &Variable)| (*a * *s).sum());
+        check!((&a, &s), |(a, s): &(&Tensor2D, &Variable)| (*a + *s).sum());
+        check!((&a, &s), |(a, s): &(&Tensor2D, &Variable)| (*a - *s).sum());
+        check!((&a, &s), |(a, s): &(&Tensor2D, &Variable)| (*s - *a).sum());
+        check!((&a, &s), |(a, s): &(&Tensor2D, &Variable)| (*a / *s).sum());
+        check!(a, |a: &Tensor2D| (-a).sum());
+    }
+
+    #[test]
+    fn tensor2d_views() {
+        let a = Tensor2D::from(&vec![vec![0.3, -0.8, 1.2], vec![0.5, 0.9, -0.1]]);
+        // weights tell the positions apart, so a view routing grads to the wrong element fails
+        let w = Tensor2D::from(&vec![vec![1., -2.], vec![3., 0.5], vec![-1.5, 2.5]]);
+        let x = Tensor1D::from(&vec![0.4, -0.2, 0.9]);
+        let y = Tensor1D::from(&vec![-1.3, 0.6]);
+        check!(a, |a: &Tensor2D| a.t().hadamard(&w).sum());
+        check!(a, |a: &Tensor2D| a.t().pow(2.0).t().sin().sum());
+        check!(a, |a: &Tensor2D| a.row(1).dot(&x).sin());
+        check!(a, |a: &Tensor2D| a.col(2).dot(&y) * a.col(0).sum());
+        check!(a, |a: &Tensor2D| a
+            .slice(0..2, 1..3)
+            .hadamard(&a.slice(0..2, 0..2))
+            .sum());
+        check!(a, |a: &Tensor2D| a.slice(1..2, 0..3).exp().sum());
+        check!(a, |a: &Tensor2D| a.reshape(3, 2).hadamard(&w).sum());
+        check!(a, |a: &Tensor2D| (&a.reshape(3, 2) * a).sin().sum());
+    }
+
+    #[test]
+    fn tensor2d_reductions() {
+        let a = Tensor2D::from(&vec![vec![0.3, -0.8, 1.2], vec![0.5, 0.9, -0.1]]);
+        for axis in 0..2 {
+            check!(a, |a: &Tensor2D| a.sum_axis(axis).exp().sum());
+            check!(a, |a: &Tensor2D| a.mean_axis(axis).exp().sum());
+            check!(a, |a: &Tensor2D| a.max_axis(axis).exp().sum());
+            check!(a, |a: &Tensor2D| a.min_axis(axis).exp().sum());
+            check!(a, |a: &Tensor2D| a.var_axis(axis).exp().sum());
+            check!(a, |a: &Tensor2D| a.std_axis(axis).exp().sum());
+        }
+        let v = Tensor2D::from(&vec![vec![1., -2., 0.5], vec![3., 0.2, -1.]]);
+        let y = Tensor1D::from(&vec![-1.3, 0.6]);
+        check!(a, |a: &Tensor2D| a.softmax().hadamard(&v).sum());
+        check!(a, |a: &Tensor2D| a.log_softmax().hadamard(&v).sum());
+        check!(a, |a: &Tensor2D| a.logsumexp().dot(&y));
+    }
+
+    #[test]
+    fn single_precision() {
+        let x = Tensor1D::<f32>::from_values(&[0.4, -0.2, 0.9]);
+        let f = |x: &Tensor1D<f32>| (x.softmax() * x.sum()).sum() + x.l2_norm();
+        gradcheck(f, &x, 1e-2, 1e-2).unwrap();
+    }
+
+    #[test]
+    fn reports_wrong_backward() {
+        // the old ln backward: -grad / x^2 instead of grad / x
+        let buggy_ln = |x: &Variable| {
+            Variable::new(VariableData {
+                data: x.data().ln(),
+                op: Some(Operation::Ln),
+                children: vec![x.clone()],
+                fun: Some(Box::new(|out: &VariableData| {
+                    let x = out.children[0].data();
+                    out.children[0].borrow_mut().grad += -out.grad / x.powf(2.0);
+                })),
+                ..VariableData::default()
+            })
+        };
+        let x = Variable::from(2.0);
+        let err = gradcheck(|x: &Variable| buggy_ln(&x.sin()).exp(), &x, EPS, TOL).unwrap_err();
+        assert_eq!(err.input, 0);
+        assert_eq!(err.op, Some(Operation::Ln));
+        assert!(err.to_string().contains("Ln"));
+    }
+}
@@ -0,0 +1,143 @@
+use rust_minigrad::{Operation, Tensor1D, Tensor2D, Variable};
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn unique_ids() {
+        let ids = (0..100_000)
+            .map(|_| Variable::<f64>::default().borrow().id)
+            .collect::<HashSet<_>>();
+        assert_eq!(ids.len(), 100_000);
+    }
+
+    #[test]
+    fn wide_graph_grad() {
+        // 100k leaves, 100k pow nodes and a single sum node on top.
+        let n = 100_000;
+        let x = Tensor1D::from(&(0..n).map(|i| i as f64).collect::<Vec<_>>());
+        let mut y = x.pow(2.0).sum();
+        y.backward();
+        x.borrow().iter().enumerate().for_each(|(i, leaf)| {
+            assert_eq!(leaf.grad(), 2.0 * i as f64, "leaf {} has a wrong grad", i);
+        });
+    }
+
+    #[test]
+    fn deep_chain_backward() {
+        let x = Variable::from(1.0);
+        let mut y = x.clone();
+        for _ in 0..1_000_000 {
+            y = &y + &x;
+        }
+        y.backward();
+        assert_eq!(x.grad(), 1_000_001.0);
+    }
+
+    #[test]
+    fn deep_chain_drop() {
+        for _ in 0..3 {
+            let mut y = Variable::from(0.0);
+            for _ in 0..1_000_000 {
+                y = y.sin();
+            }
+        }
+    }
+
+    #[test]
+    fn op_kinds() {
+        let x = Variable::from(0.5);
+        let op = |v: Variable| v.borrow().op.clone();
+        assert_eq!(op(x.pow(3.0)), Some(Operation::Pow(3.0)));
+        assert_eq!(op(x.sin()), Some(Operation::Sin));
+        assert_eq!(op(x.cos()), Some(Operation::Cos));
+        assert_eq!(op(x.exp()), Some(Operation::Exp));
+        assert_eq!(op(x.ln()), Some(Operation::Ln));
+        assert_eq!(op(x.tan()), Some(Operation::Tan));
+        assert_eq!(op(x.relu()), Some(Operation::Relu));
+        assert_eq!(op(&x / 2.0), Some(Operation::DIV));
+        assert_eq!(op(x.sigmoid()), Some(Operation::Pow(-1.0)));
+
+        let t = Tensor1D::from(&vec![1., 2.]);
+        assert_eq!(op(t.sum()), Some(Operation::Sum));
+        let m = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        assert_eq!(op(m.sum()), Some(Operation::Sum));
+    }
+
+    #[test]
+    fn dot_export() {
+        let x = Variable::from(2.0);
+        let mut y = (&x * &x).sin();
+        y.backward();
+        let dot = y.to_dot();
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.trim_end().ends_with('}'));
+        // x, x * x and sin
+        assert_eq!(dot.matches("shape=record").count(), 3);
+        // x -> mul twice, mul -> sin
+        assert_eq!(dot.matches(" -> ").count(), 3);
+        assert!(dot.contains("{ Sin | data "));
+        assert!(dot.contains("{ leaf | data 2.0000 | grad "));
+    }
+
+    #[test]
+    fn dot_collapsed_sum() {
+        let x = Tensor1D::from(&vec![1., 2., 3., 4.]);
+        let y = x.exp().sum();
+        assert_eq!(y.to_dot().matches("shape=record").count(), 9);
+
+        let dot = y.to_dot_with(true);
+        assert_eq!(dot.matches("shape=record").count(), 1);
+        assert!(dot.contains("label=\"4 inputs\""));
+    }
+
+    #[test]
+    fn dot_matmul_hub() {
+        let a = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        let dot = (&a * &a).sum().to_dot();
+        assert!(dot.contains("label=\"{ MatMulHub \\{ n: 2, k: 2, m: 2 \\} }\""));
+        // both operands, the product with the grads of its entries, and the sum
+        assert_eq!(dot.matches("| data ").count(), 4 + 4 + 4 + 1);
+        assert_eq!(dot.matches("MatMulGrad").count(), 4);
+    }
+
+    #[test]
+    fn repeated_backward() {
+        let x = Variable::from(3.0);
+        let y = &x * &x;
+        let mut z = &y * &y;
+
+        z.backward();
+        assert_eq!(x.grad(), 108.0);
+        assert_eq!(y.grad(), 18.0);
+
+        // intermediate grads are recomputed, leaves accumulate
+        z.backward();
+        assert_eq!(y.grad(), 18.0);
+        assert_eq!(x.grad(), 216.0);
+
+        z.backward_with(false);
+        assert_eq!(y.grad(), 18.0);
+        assert_eq!(x.grad(), 108.0);
+    }
+
+    #[test]
+    fn zero_grad_graph() {
+        let x = Variable::from(3.0);
+        let w = Variable::from(0.5);
+        let mut z = (&x * &w).sin();
+        z.backward();
+        assert_ne!(w.grad(), 0.0);
+
+        z.zero_grad_graph();
+        assert_eq!(x.grad(), 0.0);
+        assert_eq!(w.grad(), 0.0);
+        assert_eq!(z.grad(), 0.0);
+        assert_eq!(z.borrow().children[0].grad(), 0.0);
+
+        z.backward();
+        assert_eq!(w.grad(), 3.0 * 1.5_f64.cos());
+    }
+}
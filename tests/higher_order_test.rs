@@ -0,0 +1,98 @@
+#[macro_use]
+mod common;
+use rust_minigrad::{grad, no_grad, Tensor1D, Variable};
+use std::slice::from_ref;
+
+/// d^2 f / dx^2 at `x0`
+fn second(f: fn(&Variable) -> Variable, x0: f64) -> f64 {
+    let x = Variable::from(x0);
+    let dx = grad(&f(&x), from_ref(&x)).remove(0);
+    grad(&dx, &[x]).remove(0).data()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn first_order_matches_backward() {
+        let x = Variable::from(0.7);
+        let mut y = (&x * x.sin()).exp() / (&x + 2.0);
+        let dx = grad(&y, from_ref(&x)).remove(0);
+        y.backward();
+        assert_close!(dx.data(), x.grad(), 1e-9);
+    }
+
+    #[test]
+    fn second_order_unary() {
+        let x0 = 0.8_f64;
+        assert_close!(second(|x| x.pow(3.0), x0), 6.0 * x0, 1e-9);
+        assert_close!(second(|x| x.sin(), x0), -x0.sin(), 1e-9);
+        assert_close!(second(|x| x.cos(), x0), -x0.cos(), 1e-9);
+        assert_close!(second(|x| x.exp(), x0), x0.exp(), 1e-9);
+        assert_close!(second(|x| x.ln(), x0), -1.0 / (x0 * x0), 1e-9);
+        assert_close!(
+            second(|x| x.tan(), x0),
+            2.0 * x0.tan() / x0.cos().powi(2),
+            1e-9
+        );
+        assert_close!(second(|x| 1.0 / x, x0), 2.0 / x0.powi(3), 1e-9);
+        assert_close!(second(|x| (x * x).relu(), x0), 2.0, 1e-9);
+        let s = 1.0 / (1.0 + (-x0).exp());
+        assert_close!(
+            second(|x| x.sigmoid(), x0),
+            s * (1.0 - s) * (1.0 - 2.0 * s),
+            1e-9
+        );
+    }
+
+    #[test]
+    fn third_order() {
+        let x = Variable::from(1.5);
+        let y = x.pow(4.0);
+        let d1 = grad(&y, from_ref(&x)).remove(0);
+        let d2 = grad(&d1, from_ref(&x)).remove(0);
+        let d3 = grad(&d2, from_ref(&x)).remove(0);
+        assert_close!(d3.data(), 24.0 * 1.5, 1e-9);
+    }
+
+    #[test]
+    fn hessian_vector_product() {
+        // f(x, y) = x^2 y + y^3, H = [[2y, 2x], [2x, 6y]]
+        let (x, y) = (Variable::from(1.0), Variable::from(2.0));
+        let f = &x * &x * &y + y.pow(3.0);
+        let g = grad(&f, &[x.clone(), y.clone()]);
+        let v = [3.0, -1.0];
+        let gv = &g[0] * v[0] + &g[1] * v[1];
+        let hv = grad(&gv, &[x, y]);
+        assert_close!(hv[0].data(), 4.0 * 3.0 + -2.0, 1e-9);
+        assert_close!(hv[1].data(), 2.0 * 3.0 + -12.0, 1e-9);
+    }
+
+    #[test]
+    fn gradient_penalty() {
+        // loss = (d/dx (w * x^2))^2 = (2 w x)^2, d loss / dw = 8 w x^2
+        let (w, x) = (Variable::from(0.5), Variable::from(3.0));
+        let out = Tensor1D::from(&vec![1.0]) * (&w * &x * &x);
+        let dx = grad(&out.sum(), from_ref(&x)).remove(0);
+        let mut penalty = dx.pow(2.0);
+        penalty.backward();
+        assert_close!(w.grad(), 8.0 * 0.5 * 9.0, 1e-9);
+    }
+
+    #[test]
+    fn no_grad_returns_values_only() {
+        let x = Variable::from(2.0);
+        let y = x.pow(3.0);
+        let dx = no_grad(|| grad(&y, from_ref(&x))).remove(0);
+        assert_close!(dx.data(), 12.0, 1e-9);
+        assert!(dx.borrow().children.is_empty());
+    }
+
+    #[test]
+    fn unrelated_input() {
+        let (x, y) = (Variable::from(1.0), Variable::from(2.0));
+        let g = grad(&x.sin(), &[y]);
+        assert_eq!(g[0].data(), 0.0);
+    }
+}
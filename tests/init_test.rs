@@ -0,0 +1,151 @@
+#[macro_use]
+mod common;
+use common::values;
+use rand::{rngs::StdRng, SeedableRng};
+use rust_minigrad::{Tensor1D, Tensor2D, Variable};
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn filled() {
+        assert_eq!(values(&Tensor1D::ones(3)), vec![1.; 3]);
+        assert_eq!(values(&Tensor1D::full(2, -0.5)), vec![-0.5; 2]);
+        let m = Tensor2D::full(2, 3, 7.);
+        assert_eq!(m.shape(), (2, 3));
+        assert_eq!(values(&m).concat(), vec![7.; 6]);
+        assert_eq!(values(&Tensor2D::ones(1, 2)).concat(), vec![1.; 2]);
+        assert_eq!(
+            values(&Tensor2D::eye(3)).concat(),
+            vec![1., 0., 0., 0., 1., 0., 0., 0., 1.]
+        );
+        let x = Tensor1D::<f32>::ones(2);
+        assert_eq!(x.borrow()[1].data(), 1f32);
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(
+            values(&Tensor1D::arange(0., 5., 1.)),
+            vec![0., 1., 2., 3., 4.]
+        );
+        assert_eq!(
+            values(&Tensor1D::arange(1., 2., 0.25)),
+            vec![1., 1.25, 1.5, 1.75]
+        );
+        assert_eq!(values(&Tensor1D::arange(3., 0., -1.5)), vec![3., 1.5]);
+        assert_eq!(
+            values(&Tensor1D::linspace(0., 1., 5)),
+            vec![0., 0.25, 0.5, 0.75, 1.]
+        );
+        assert_eq!(values(&Tensor1D::linspace(2., 3., 1)), vec![2.]);
+    }
+
+    #[test]
+    fn empty() {
+        // same as NumPy: a range that can't reach its end is empty
+        assert_eq!(Tensor1D::arange(0., 5., -1.).shape(), (0,));
+        assert_eq!(Tensor1D::<f64>::arange(0., 0., 1.).shape(), (0,));
+        assert_eq!(Tensor1D::linspace(1., 2., 0).shape(), (0,));
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(Tensor1D::rand_uniform(0, 0., 1., &mut rng)
+            .borrow()
+            .is_empty());
+        assert_eq!(Tensor2D::<f64>::randn(2, 0, &mut rng).shape(), (2, 0));
+        assert_eq!(Tensor1D::from(&vec![]).shape(), (0,));
+        assert_eq!(Tensor2D::from(&vec![]).shape(), (0, 0));
+
+        // elementwise ops and broadcasting keep the empty axis
+        let (x, m) = (Tensor1D::full(0, 3.), Tensor2D::ones(0, 3));
+        assert_eq!((x.sin() + 1.0).shape(), (0,));
+        assert_eq!(m.sin().shape(), (0, 3));
+        assert_eq!((&m + 1.0).shape(), (0, 3));
+        assert_eq!((&m * Variable::from(2.)).shape(), (0, 3));
+        assert_eq!((&m + Tensor1D::ones(3)).shape(), (0, 3));
+        assert_eq!((Tensor2D::ones(2, 1) - &x).shape(), (2, 0));
+        assert_eq!(x.outer(&Tensor1D::ones(2)).shape(), (0, 2));
+        assert_eq!(Tensor1D::ones(2).outer(&x).shape(), (2, 0));
+
+        // views
+        assert_eq!(Tensor2D::<f64>::ones(2, 0).t().shape(), (0, 2));
+        assert_eq!(m.t().shape(), (3, 0));
+        assert_eq!(
+            Tensor2D::<f64>::ones(2, 3).slice(1..1, 0..3).shape(),
+            (0, 3)
+        );
+        assert_eq!(m.reshape(3, 0).shape(), (3, 0));
+
+        // products: an empty inner dimension gives zeros
+        assert_eq!((&m * &Tensor2D::ones(3, 2)).shape(), (0, 2));
+        assert_eq!(values(&(Tensor2D::ones(2, 0) * &x)), vec![0.; 2]);
+        let c = Tensor2D::ones(2, 0) * Tensor2D::ones(0, 3);
+        assert_eq!(values(&c), vec![vec![0.; 3]; 2]);
+        c.sum().backward();
+        assert_eq!(x.dot(&Tensor1D::ones(0)).data(), 0.);
+
+        // sum of nothing is 0, mean and logsumexp are NaN, softmax is empty
+        assert_eq!(x.sum().data(), 0.);
+        assert_eq!(m.sum().data(), 0.);
+        assert!(x.mean().data().is_nan());
+        assert!(m.mean().data().is_nan());
+        assert!(x.var().data().is_nan());
+        assert!(x.logsumexp().data().is_nan());
+        assert_eq!(x.softmax().shape(), (0,));
+        assert_eq!(Tensor2D::<f64>::eye(0).softmax().shape(), (0, 0));
+        assert_eq!(m.log_softmax().shape(), (0, 3));
+        assert_eq!(values(&m.sum_axis(0)), vec![0.; 3]);
+        assert_eq!(m.sum_axis(1).shape(), (0,));
+        assert!(values(&m.mean_axis(0)).iter().all(|v| v.is_nan()));
+        assert_eq!(values(&Tensor2D::ones(2, 0).sum_axis(1)), vec![0.; 2]);
+        m.sum().backward();
+        x.mean().backward();
+    }
+
+    #[test]
+    #[should_panic(expected = "max of an empty tensor")]
+    fn empty_max() {
+        Tensor1D::<f64>::ones(0).max();
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let x = values(&Tensor1D::rand_uniform(1000, -2., 3., &mut rng));
+        assert!(x.iter().all(|&v| (-2. ..3.).contains(&v)));
+        let mean = x.iter().sum::<f64>() / 1000.;
+        assert!((mean - 0.5).abs() < 0.2);
+
+        let x = values(&Tensor2D::randn(50, 40, &mut rng)).concat();
+        let mean = x.iter().sum::<f64>() / 2000.;
+        let var = x.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 2000.;
+        assert!(mean.abs() < 0.1);
+        assert!((var - 1.).abs() < 0.1);
+
+        // same seed, same samples
+        let a = Tensor1D::randn(5, &mut StdRng::seed_from_u64(7));
+        let b = Tensor1D::randn(5, &mut StdRng::seed_from_u64(7));
+        assert_eq!(values(&a), values(&b));
+    }
+
+    #[test]
+    fn initialisers() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (r, c) = (100, 300);
+        let bound = |m: &Tensor2D| values(m).concat().iter().fold(0f64, |a, v| a.max(v.abs()));
+        let std = |m: &Tensor2D| {
+            let x = values(m).concat();
+            (x.iter().map(|v| v * v).sum::<f64>() / x.len() as f64).sqrt()
+        };
+        let xu = Tensor2D::xavier_uniform(r, c, &mut rng);
+        assert_eq!(xu.shape(), (r, c));
+        assert!(bound(&xu) <= (6. / 400f64).sqrt());
+        assert!(bound(&xu) > 0.9 * (6. / 400f64).sqrt());
+        let ku = Tensor2D::kaiming_uniform(r, c, &mut rng);
+        assert!(bound(&ku) <= (6. / 300f64).sqrt());
+        let xn = Tensor2D::xavier_normal(r, c, &mut rng);
+        assert!((std(&xn) / (2. / 400f64).sqrt() - 1.).abs() < 0.05);
+        let kn = Tensor2D::kaiming_normal(r, c, &mut rng);
+        assert!((std(&kn) / (2. / 300f64).sqrt() - 1.).abs() < 0.05);
+    }
+}
@@ -0,0 +1,186 @@
+#[macro_use]
+mod common;
+use common::{grads, values};
+use rust_minigrad::{grad, hessian, no_grad, Operation, Tensor, Tensor1D, Tensor2D};
+
+fn matrix(r: usize, c: usize, f: impl Fn(usize) -> f64) -> Vec<Vec<f64>> {
+    (0..r)
+        .map(|i| (0..c).map(|j| f(i * c + j)).collect())
+        .collect()
+}
+
+fn naive(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let mut c = vec![vec![0.; b[0].len()]; a.len()];
+    for i in 0..a.len() {
+        for j in 0..b[0].len() {
+            for p in 0..b.len() {
+                c[i][j] += a[i][p] * b[p][j];
+            }
+        }
+    }
+    c
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn one_hub_per_product() {
+        let a = Tensor2D::from(&vec![vec![1., 2., 3.], vec![4., 5., 6.]]);
+        let b = Tensor2D::from(&vec![vec![1., 0.], vec![0., 1.], vec![1., 1.]]);
+        let c = &a * &b;
+        assert_eq!(c.shape(), (2, 2));
+
+        let hub = c.borrow()[0][0].borrow().children[0].clone();
+        assert_eq!(
+            hub.borrow().op,
+            Some(Operation::MatMulHub { n: 2, k: 3, m: 2 })
+        );
+        // both operands, then the grad of every entry of `c`
+        assert_eq!(hub.borrow().children.len(), 6 + 6 + 4);
+        assert_eq!(
+            hub.borrow().children[6 + 6 + 2].borrow().op,
+            Some(Operation::MatMulGrad { row: 1, col: 0 })
+        );
+        c.borrow().iter().flatten().for_each(|entry| {
+            assert!(matches!(entry.borrow().op, Some(Operation::MatMul { .. })));
+            assert_eq!(entry.borrow().children.len(), 1);
+            assert!(entry.borrow().children[0] == hub);
+        });
+        assert_eq!(
+            c.borrow()[1][0].borrow().op,
+            Some(Operation::MatMul { row: 1, col: 0 })
+        );
+        assert_eq!(values(&c), vec![vec![4., 5.], vec![10., 11.]]);
+
+        let x = Tensor1D::from(&vec![1., -1., 2.]);
+        let y = &a * &x;
+        assert_eq!(y.shape(), (2,));
+        assert_eq!(y.borrow()[1].data(), 4. - 5. + 12.);
+    }
+
+    #[test]
+    fn matmul_grads() {
+        let a = Tensor2D::from(&vec![vec![0.3, -0.8, 1.2], vec![0.5, 0.9, -0.1]]);
+        let b = Tensor2D::from(&vec![vec![0.2, 0.1], vec![-0.6, 0.4], vec![1.1, -0.7]]);
+        let x = Tensor1D::from(&vec![0.4, -0.2, 0.9]);
+        check!((&a, &b), |(a, b): &(&Tensor2D, &Tensor2D)| (*a * *b)
+            .sin()
+            .sum());
+        check!((&a, &x), |(a, x): &(&Tensor2D, &Tensor1D)| (*a * *x)
+            .exp()
+            .sum());
+        check!(a, |a: &Tensor2D| (a * &a.t()).pow(2.0).sum());
+        check!(b, |b: &Tensor2D| (&(b * &b.t()) * b).sum());
+        // the output is used twice, and only a part of it reaches the loss
+        check!((&a, &b), |(a, b): &(&Tensor2D, &Tensor2D)| {
+            let c = *a * *b;
+            c.hadamard(&c).sum() + c.row(0).sum()
+        });
+    }
+
+    #[test]
+    fn repeated_backward() {
+        let a = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        let x = Tensor1D::from(&vec![1., -1.]);
+        let mut y = (&a * &x).l2_2();
+        y.backward_with(false);
+        let first = grads(&a).concat();
+        y.backward_with(false);
+        assert_eq!(grads(&a).concat(), first);
+        y.backward();
+        assert_eq!(
+            grads(&a).concat(),
+            first.iter().map(|g| 2. * g).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn partial_backward() {
+        // the grads of the entries are part of the graph: a pass only sees the ones it reaches
+        let a = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        let b = Tensor2D::from(&vec![vec![5., 6.], vec![7., 8.]]);
+        let c = &a * &b;
+        c.borrow()[0][0].clone().backward_with(false);
+        assert_eq!(grads(&a), vec![vec![5., 7.], vec![0., 0.]]);
+        c.borrow()[1][1].clone().backward_with(false);
+        assert_eq!(grads(&a), vec![vec![0., 0.], vec![6., 8.]]);
+        assert_eq!(grads(&b), vec![vec![0., 3.], vec![0., 4.]]);
+
+        c.borrow()[1][1].zero_grad_graph();
+        let hub = c.borrow()[1][1].borrow().children[0].clone();
+        assert!(hub.borrow().children.iter().all(|x| x.grad() == 0.));
+    }
+
+    #[test]
+    fn matches_tensor() {
+        let n = 256;
+        let (av, bv) = (
+            matrix(n, n, |i| ((i % 7) as f64 - 3.) / 4.),
+            matrix(n, n, |i| ((i % 5) as f64 - 2.) / 3.),
+        );
+        let (a, b) = (Tensor2D::from(&av), Tensor2D::from(&bv));
+        let mut y = (&a * &b).sum();
+        y.backward();
+
+        let flat = |m: &[Vec<f64>]| m.iter().flatten().copied().collect::<Vec<_>>();
+        let (an, bn) = (
+            Tensor::from_vec(flat(&av), &[n, n]),
+            Tensor::from_vec(flat(&bv), &[n, n]),
+        );
+        let yn = an.matmul(&bn).sum();
+        yn.backward();
+        assert!((y.data() - yn.item()).abs() < 1e-9);
+        assert_eq!(grads(&a).concat(), an.grad());
+        assert_eq!(grads(&b).concat(), bn.grad());
+    }
+
+    #[test]
+    fn matches_naive_loop() {
+        // every dimension spans more than one block of the kernel
+        let (n, k, m) = (70, 300, 530);
+        let av = matrix(n, k, |i| ((i * 7 % 11) as f64 - 5.) / 3.);
+        let bv = matrix(k, m, |i| ((i * 5 % 13) as f64 - 6.) / 7.);
+        let expected = naive(&av, &bv);
+        let c = values(&(&Tensor2D::from(&av) * &Tensor2D::from(&bv)));
+        for (row, expected) in c.iter().zip(&expected) {
+            for (x, y) in row.iter().zip(expected) {
+                assert!((x - y).abs() < 1e-9, "{} != {}", x, y);
+            }
+        }
+
+        let x = Tensor1D::from(&bv.iter().map(|row| row[3]).collect::<Vec<_>>());
+        let y = &Tensor2D::from(&av) * &x;
+        for (y, expected) in y.borrow().iter().zip(&expected) {
+            assert!((y.data() - expected[3]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn product_under_no_grad() {
+        let a = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        let c = no_grad(|| &a * &a);
+        assert_eq!(c.borrow()[1][1].data(), 22.);
+        assert!(c.borrow()[1][1].borrow().children.is_empty());
+    }
+
+    #[test]
+    fn hessian_through_product() {
+        // the hessian of |Ax|^2 is 2 A^T A
+        let a = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        let x = Tensor1D::from(&vec![1., -1.]);
+        let h = hessian(|x| (&a * x).l2_2(), &x);
+        assert_eq!(values(&h), vec![vec![20., 28.], vec![28., 40.]]);
+
+        // second derivatives w.r.t. entries of both operands
+        let b = Tensor2D::from(&vec![vec![0.5, -1.], vec![2., 0.3]]);
+        let y = (&a * &b).pow(2.0).sum();
+        let g = grad(&y, &[a.borrow()[0][1].clone()]);
+        let h = grad(&g[0], &[a.borrow()[0][1].clone(), b.borrow()[1][0].clone()]);
+        // y = sum_ij (a_i . b_j)^2, d^2 y / d a_01^2 = 2 |b_1.|^2
+        assert_close!(h[0].data(), 2. * (2f64.powi(2) + 0.3f64.powi(2)), 1e-12);
+        // d^2 y / d a_01 d b_10 = 2 (a_0 . b_.0) + 2 a_01 b_10
+        assert_close!(h[1].data(), 2. * (1. * 0.5 + 2. * 2.) + 2. * 2. * 2., 1e-12);
+    }
+}
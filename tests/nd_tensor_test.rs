@@ -0,0 +1,178 @@
+#[macro_use]
+mod common;
+use rust_minigrad::{no_grad, Tensor, Tensor1D, Tensor2D, TensorOp};
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn shape_and_strides() {
+        let x = Tensor::from_vec((0..24).map(|i| i as f64).collect(), &[2, 3, 4]);
+        assert_eq!(x.shape(), vec![2, 3, 4]);
+        assert_eq!(x.strides(), vec![12, 4, 1]);
+        assert_eq!(x.get(&[1, 2, 3]), 23.0);
+        assert_eq!(x.get(&[1, 0, 2]), 14.0);
+
+        let y = x.reshape(&[4, 6]);
+        assert_eq!(y.strides(), vec![6, 1]);
+        assert_eq!(y.get(&[2, 1]), 13.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_shape() {
+        Tensor::from_vec(vec![1.0, 2.0, 3.0], &[2, 2]);
+    }
+
+    #[test]
+    fn elementwise_grads() {
+        let x = Tensor::from_vec(vec![0.3, -0.8, 1.2, 0.5, 0.9, -0.1], &[2, 3]);
+        check!(x, |x: &Tensor| x.sum());
+        check!(x, |x: &Tensor| x.mean());
+        check!(x, |x: &Tensor| (x * x + x).sum());
+        check!(x, |x: &Tensor| (x - x * 2.0).sum());
+        check!(x, |x: &Tensor| (x / (x * x + 1.0)).sum());
+        check!(x, |x: &Tensor| (-x).exp().sum());
+        check!(x, |x: &Tensor| (x * x + 0.5).ln().sum());
+        check!(x, |x: &Tensor| (x.sin() * x.cos()).sum());
+        check!(x, |x: &Tensor| x.relu().sum());
+        check!(x, |x: &Tensor| x.pow(3.0).sum());
+        check!(x, |x: &Tensor| (x.t() * x.t().relu()).sum());
+        check!(x, |x: &Tensor| x.reshape(&[3, 2]).sin().sum());
+    }
+
+    #[test]
+    fn broadcasting() {
+        let m = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[2, 3]);
+        let row = Tensor::from_vec(vec![10.0, 20.0, 30.0], &[3]);
+        let col = Tensor::from_vec(vec![2.0, -1.0], &[2, 1]);
+        let y = &m + &row;
+        assert_eq!(y.shape(), vec![2, 3]);
+        assert_eq!(y.data(), vec![11.0, 22.0, 33.0, 14.0, 25.0, 36.0]);
+        assert_eq!(
+            (&col * &row).data(),
+            vec![20.0, 40.0, 60.0, -10.0, -20.0, -30.0]
+        );
+        assert_eq!((&m / 2.0).get(&[1, 0]), 2.0);
+
+        // the grad of a repeated element is summed over its repeats
+        (&(&m * &col) + &row).sum().backward();
+        assert_eq!(row.grad(), vec![2.0; 3]);
+        assert_eq!(col.grad(), vec![6.0, 15.0]);
+
+        let x = Tensor::from_vec(vec![0.3, -0.8, 1.2, 0.5, 0.9, -0.1], &[2, 1, 3]);
+        let v = Tensor::from_vec(vec![0.7, 1.3, -0.4, 2.1], &[4, 1]);
+        check!((&x, &v), |(x, v): &(&Tensor, &Tensor)| (*x * *v)
+            .sin()
+            .sum());
+        check!((&x, &v), |(x, v): &(&Tensor, &Tensor)| (*x
+            / (*v * *v + 1.0))
+            .sum());
+        check!((&x, &v), |(x, v): &(&Tensor, &Tensor)| (*v - *x)
+            .exp()
+            .sum());
+    }
+
+    #[test]
+    #[should_panic(expected = "can't broadcast")]
+    fn broadcast_mismatch() {
+        let _ = Tensor::from_vec(vec![1.0; 6], &[2, 3]) + Tensor::from_vec(vec![1.0; 2], &[2]);
+    }
+
+    #[test]
+    fn matmul_grads() {
+        let a = Tensor::from_vec(vec![0.3, -0.8, 1.2, 0.5, 0.9, -0.1], &[2, 3]);
+        let b = Tensor::from_vec(vec![0.2, 0.1, -0.6, 0.4, 1.1, -0.7], &[3, 2]);
+        let v = Tensor::from_vec(vec![0.4, -0.2, 0.9], &[3]);
+        check!(a, |a: &Tensor| a.matmul(&b).sin().sum());
+        check!(b, |b: &Tensor| a.matmul(b).sin().sum());
+        check!(a, |a: &Tensor| a.matmul(&v).exp().sum());
+        check!(v, |v: &Tensor| a.matmul(v).exp().sum());
+        check!(a, |a: &Tensor| a.matmul(&a.t()).sum());
+    }
+
+    #[test]
+    fn matches_scalar_tensors() {
+        let a = vec![vec![1.0, 2.0, -1.0], vec![0.5, -3.0, 2.0]];
+        let b = vec![vec![0.2, 0.1], vec![-0.6, 0.4], vec![1.1, -0.7]];
+
+        let (a2, b2) = (Tensor2D::from(&a), Tensor2D::from(&b));
+        let mut y2 = (&a2 * &b2).sum();
+        y2.backward();
+
+        let flat = |m: &[Vec<f64>]| m.iter().flatten().copied().collect::<Vec<_>>();
+        let (an, bn) = (
+            Tensor::from_vec(flat(&a), &[2, 3]),
+            Tensor::from_vec(flat(&b), &[3, 2]),
+        );
+        let yn = an.matmul(&bn).sum();
+        yn.backward();
+
+        assert_close!(yn.item(), y2.data(), 1e-12);
+        let grads2 = |m: &Tensor2D| m.borrow().iter().flatten().map(|x| x.grad()).collect();
+        let (ga, gb): (Vec<f64>, Vec<f64>) = (grads2(&a2), grads2(&b2));
+        an.grad()
+            .iter()
+            .zip(ga)
+            .for_each(|(x, y)| assert_close!(x, y, 1e-12));
+        bn.grad()
+            .iter()
+            .zip(gb)
+            .for_each(|(x, y)| assert_close!(x, y, 1e-12));
+
+        let x = Tensor1D::from(&vec![0.4, -0.2, 0.9]);
+        let xn = Tensor::from_vec(vec![0.4, -0.2, 0.9], &[3]);
+        let y = (&a2 * &x).relu().sum();
+        assert_close!(an.matmul(&xn).relu().sum().item(), y.data(), 1e-12);
+    }
+
+    #[test]
+    fn one_node_per_op() {
+        let a = Tensor::from_vec(vec![1.0; 6], &[2, 3]);
+        let b = Tensor::from_vec(vec![1.0; 12], &[3, 4]);
+        let c = a.matmul(&b);
+        assert_eq!(c.shape(), vec![2, 4]);
+        assert_eq!(c.borrow().op, Some(TensorOp::MatMul));
+        assert_eq!(c.borrow().children.len(), 2);
+        assert_eq!(c.data(), vec![3.0; 8]);
+    }
+
+    #[test]
+    fn large_matmul_backward() {
+        let n = 256;
+        let a = Tensor::from_vec((0..n * n).map(|i| (i % 7) as f64).collect(), &[n, n]);
+        let b = Tensor::from_vec((0..n * n).map(|i| (i % 5) as f64).collect(), &[n, n]);
+        a.matmul(&b).sum().backward();
+        // d sum(AB) / dA_ij = sum_k B_jk
+        let row = |j: usize| (0..n).map(|k| ((j * n + k) % 5) as f64).sum::<f64>();
+        assert_eq!(a.grad()[3 * n + 7], row(7));
+    }
+
+    #[test]
+    fn step_and_zero_grad() {
+        let w = Tensor::from_vec(vec![1.0, -2.0], &[2]);
+        (&w * &w).sum().backward();
+        assert_eq!(w.grad(), vec![2.0, -4.0]);
+        w.step(0.5);
+        assert_eq!(w.data(), vec![0.0, 0.0]);
+        w.zero_grad();
+        assert_eq!(w.grad(), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn no_grad_tensor() {
+        let x = Tensor::from_vec(vec![1.0, 2.0], &[2]);
+        let y = no_grad(|| x.exp().sum());
+        assert!(y.borrow().children.is_empty());
+        assert!(y.borrow().fun.is_none());
+    }
+
+    #[test]
+    fn deep_chain_drop() {
+        let mut y = Tensor::from_vec(vec![0.0; 4], &[2, 2]);
+        for _ in 0..1_000_000 {
+            y = y.sin();
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use rust_minigrad::{is_grad_enabled, no_grad, NoGradGuard, Tensor1D, Tensor2D, Variable};
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn no_grad_scope() {
+        let x = Variable::from(2.0);
+        let y = no_grad(|| (&x * &x).sin() + 1.0);
+        assert_eq!(y.data(), 4.0_f64.sin() + 1.0);
+        assert!(y.borrow().children.is_empty());
+        assert!(y.borrow().fun.is_none());
+        assert!(is_grad_enabled());
+
+        let z = &x * &x;
+        assert_eq!(z.borrow().children.len(), 2);
+    }
+
+    #[test]
+    fn guard_restores_previous_state() {
+        {
+            let _outer = NoGradGuard::new();
+            {
+                let _inner = NoGradGuard::new();
+                assert!(!is_grad_enabled());
+            }
+            assert!(!is_grad_enabled());
+        }
+        assert!(is_grad_enabled());
+    }
+
+    #[test]
+    fn tensors_under_no_grad() {
+        let _guard = NoGradGuard::new();
+        let w = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        let x = Tensor1D::from(&vec![1., 1.]);
+        let y = (&w * &x).relu().sum();
+        assert_eq!(y.data(), 10.0);
+        assert!(y.borrow().children.is_empty());
+    }
+
+    #[test]
+    fn backward_of_no_grad_result() {
+        let x = Variable::from(3.0);
+        let mut y = no_grad(|| &x * &x);
+        y.backward();
+        assert_eq!(x.grad(), 0.0);
+    }
+}
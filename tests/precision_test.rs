@@ -0,0 +1,122 @@
+#[macro_use]
+mod common;
+use rust_minigrad::{grad, hessian, jacobian, CustomOp, Scalar, Tensor1D, Tensor2D, Variable};
+
+/// (x, y) -> x * y^2, for any precision
+struct MulSq;
+
+impl<T: Scalar> CustomOp<T> for MulSq {
+    fn name(&self) -> &str {
+        "mul_sq"
+    }
+
+    fn forward(&self, inputs: &[T]) -> T {
+        inputs[0] * inputs[1] * inputs[1]
+    }
+
+    fn backward(&self, inputs: &[T], _output: T, grad: T) -> Vec<T> {
+        let two = T::from_f64(2.0);
+        vec![
+            grad * inputs[1] * inputs[1],
+            grad * two * inputs[0] * inputs[1],
+        ]
+    }
+}
+
+/// The same tests, once per precision.
+macro_rules! precision_tests {
+    ($name:ident, $t:ty, $tol:expr) => {
+        #[cfg(test)]
+        mod $name {
+            use crate::*;
+
+            type V = Variable<$t>;
+            const TOL: $t = $tol;
+
+            #[test]
+            fn scalar_ops() {
+                let (x, y) = (V::from_value(0.7), V::from_value(-1.3));
+                let mut z = (&x * &y + 2.0 - &x / &y).sin() + 1.0 / &x;
+                z.backward();
+                let (x0, y0): ($t, $t) = (0.7, -1.3);
+                let c = (x0 * y0 + 2.0 - x0 / y0).cos();
+                assert_close!(z.data(), (x0 * y0 + 2.0 - x0 / y0).sin() + 1.0 / x0, TOL);
+                assert_close!(x.grad(), c * (y0 - 1.0 / y0) - 1.0 / (x0 * x0), TOL);
+                assert_close!(y.grad(), c * (x0 + x0 / (y0 * y0)), TOL);
+            }
+
+            #[test]
+            fn unary_ops() {
+                let x0: $t = 0.4;
+                let x = V::from_value(x0);
+                let mut y = x.exp().ln() + x.pow(3.0) + x.tan() + x.cos() + x.relu() + x.silu();
+                y.backward();
+                let s = 1.0 / (1.0 + (-x0).exp());
+                let expected = 1.0 + 3.0 * x0 * x0 + 1.0 / (x0.cos() * x0.cos()) - x0.sin()
+                    + 1.0
+                    + s * (1.0 + x0 * (1.0 - s));
+                assert_close!(x.grad(), expected, TOL);
+            }
+
+            #[test]
+            fn tensor_ops() {
+                let w = Tensor2D::<$t>::from_values(&[vec![1.0, 2.0], vec![3.0, -4.0]]);
+                let x = Tensor1D::<$t>::from_values(&[0.5, 0.25]);
+                let y = (&w * &x).relu().sum() + (&w * &w).mean() * x.l2_2();
+                y.clone().backward();
+                // w x = (1, 0.5), w w = [[7, -6], [-9, 22]]
+                assert_close!(y.data(), 1.5 + 3.5 * 0.3125, TOL);
+                assert_close!(x.borrow()[0].grad(), 4.0 + 3.5 * 2.0 * 0.5, TOL);
+                assert_close!(x.borrow()[1].grad(), -2.0 + 3.5 * 2.0 * 0.25, TOL);
+            }
+
+            #[test]
+            fn softmax_sums_to_one() {
+                let x = Tensor1D::<$t>::from_values(&[0.1, -2.0, 3.0]);
+                let p = x.softmax();
+                let total = p.sum();
+                assert_close!(total.data(), 1.0, TOL);
+            }
+
+            #[test]
+            fn custom_op() {
+                let (x, y) = (V::from_value(1.5), V::from_value(-2.0));
+                let mut z = Variable::custom(MulSq, &[x.clone(), y.clone()]);
+                z.backward();
+                assert_close!(z.data(), 6.0, TOL);
+                assert_close!(x.grad(), 4.0, TOL);
+                assert_close!(y.grad(), -6.0, TOL);
+            }
+
+            #[test]
+            fn second_order() {
+                let x = V::from_value(0.8);
+                let dx = grad(&x.pow(3.0), std::slice::from_ref(&x)).remove(0);
+                let d2x = grad(&dx, &[x]).remove(0);
+                assert_close!(d2x.data(), 6.0 * 0.8, TOL);
+            }
+
+            #[test]
+            fn jacobian_and_hessian() {
+                let x = Tensor1D::<$t>::from_values(&[1.0, 2.0]);
+                let j = jacobian(|x| x.pow(2.0), &x);
+                assert_close!(j.borrow()[1][1].data(), 4.0, TOL);
+                assert_close!(j.borrow()[0][1].data(), 0.0, TOL);
+                // f = x0^2 x1
+                let h = hessian(
+                    |x| {
+                        let x = x.borrow();
+                        &x[0] * &x[0] * &x[1]
+                    },
+                    &x,
+                );
+                assert_close!(h.borrow()[0][0].data(), 4.0, TOL);
+                assert_close!(h.borrow()[0][1].data(), 2.0, TOL);
+                assert_close!(h.borrow()[1][1].data(), 0.0, TOL);
+            }
+        }
+    };
+}
+
+precision_tests!(f32_tests, f32, 1e-5);
+precision_tests!(f64_tests, f64, 1e-12);
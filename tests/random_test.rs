@@ -0,0 +1,86 @@
+#[macro_use]
+mod common;
+use common::values;
+use rust_minigrad::{manual_seed, shuffle, with_rng, Tensor1D, Tensor2D};
+
+/// Trains a small layer with dropout on shuffled samples, returns the losses and the weights.
+fn train(seed: u64) -> (Vec<f64>, Vec<f64>) {
+    manual_seed(seed);
+    let w = with_rng(|rng| Tensor2D::xavier_uniform(2, 3, rng));
+    let mut samples = (0..8)
+        .map(|i| {
+            let x = i as f64 / 8.;
+            ([x, 1. - x, x * x], [x.sin(), x.cos()])
+        })
+        .collect::<Vec<_>>();
+    let mut losses = Vec::new();
+    for _ in 0..3 {
+        shuffle(&mut samples);
+        for (x, y) in &samples {
+            let out = (&w * &Tensor1D::from(&x.to_vec())).dropout(0.5);
+            let mut loss = out.mse_loss(&Tensor1D::from(&y.to_vec()));
+            loss.backward();
+            w.borrow().iter().flatten().for_each(|p| {
+                p.step(0.1);
+                p.zero_grad();
+            });
+            losses.push(loss.data());
+        }
+    }
+    let weights = w.borrow().iter().flatten().map(|p| p.data()).collect();
+    (losses, weights)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn same_seed_same_run() {
+        let (losses, weights) = train(3);
+        assert_eq!(train(3), (losses.clone(), weights));
+        assert_ne!(train(4).0, losses);
+    }
+
+    #[test]
+    fn seeded_draws() {
+        manual_seed(1);
+        let a = with_rng(|rng| Tensor1D::randn(4, rng));
+        let mut v = (0..10).collect::<Vec<_>>();
+        shuffle(&mut v);
+        manual_seed(1);
+        let b = with_rng(|rng| Tensor1D::randn(4, rng));
+        let mut u = (0..10).collect::<Vec<_>>();
+        shuffle(&mut u);
+        assert_eq!(values(&a), values(&b));
+        assert_eq!(u, v);
+        u.sort();
+        assert_eq!(u, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn dropout() {
+        manual_seed(0);
+        let x = Tensor1D::ones(1000);
+        let y = values(&x.dropout(0.25));
+        assert!(y.iter().all(|&v| v == 0. || v == 1. / 0.75));
+        let kept = y.iter().filter(|&&v| v > 0.).count();
+        assert!((700..800).contains(&kept));
+        assert_eq!(values(&x.dropout(0.)), vec![1.; 1000]);
+
+        // dropped elements get no grad
+        let mut s = x.dropout(0.5).sum();
+        s.backward();
+        let g = x.borrow().iter().map(|v| v.grad()).collect::<Vec<_>>();
+        assert!(g.iter().all(|&g| g == 0. || g == 2.));
+
+        let m = Tensor2D::ones(2, 3).dropout(0.5);
+        assert_eq!(m.shape(), (2, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "dropout probability")]
+    fn dropout_all() {
+        Tensor1D::ones(3).dropout(1.);
+    }
+}
@@ -0,0 +1,102 @@
+#[macro_use]
+mod common;
+use common::values;
+use rust_minigrad::{grad, Operation, Tensor1D, Tensor2D};
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn small_logits() {
+        let x = Tensor1D::from(&vec![1., 2., 3.]);
+        let total = 1f64.exp() + 2f64.exp() + 3f64.exp();
+        assert_close!(x.logsumexp().data(), total.ln(), 1e-12);
+        let p = values(&x.softmax());
+        let logp = values(&x.log_softmax());
+        for i in 0..3 {
+            assert_close!(p[i], ((i + 1) as f64).exp() / total, 1e-12);
+            assert_close!(logp[i], p[i].ln(), 1e-12);
+        }
+    }
+
+    #[test]
+    fn large_logits_stay_finite() {
+        let x = Tensor1D::from(&vec![1000., 2000., 3000.]);
+        assert_close!(x.logsumexp().data(), 3000., 1e-9);
+        assert_eq!(values(&x.softmax()), vec![0., 0., 1.]);
+        assert_eq!(values(&x.log_softmax()), vec![-2000., -1000., 0.]);
+
+        let target = Tensor1D::from(&vec![0., 1., 0.]);
+        let mut loss = target.cross_entropy_with_logits(&x);
+        loss.backward();
+        assert_close!(loss.data(), 1000., 1e-9);
+        let g = x.borrow().iter().map(|v| v.grad()).collect::<Vec<_>>();
+        assert!(g.iter().all(|g| g.is_finite()));
+        assert_close!(g[1], -1., 1e-12);
+        assert_close!(g[2], 1., 1e-12);
+
+        let m = Tensor2D::from(&vec![vec![-5000., 5000.], vec![1e4, 1e4]]);
+        let p = m.softmax();
+        assert_eq!(p.borrow()[0][1].data(), 1.);
+        assert_close!(p.borrow()[1][0].data(), 0.5, 1e-12);
+        assert_close!(m.logsumexp().borrow()[1].data(), 1e4 + 2f64.ln(), 1e-9);
+    }
+
+    #[test]
+    fn dedicated_nodes() {
+        let x = Tensor1D::from(&vec![0.5, -1., 2.]);
+        let lse = x.logsumexp();
+        assert_eq!(lse.borrow().op, Some(Operation::LogSumExp));
+        assert_eq!(lse.borrow().children.len(), 3);
+
+        let p = x.softmax();
+        let node = p.borrow()[0].clone();
+        assert_eq!(node.borrow().op, Some(Operation::Softmax));
+        assert_eq!(node.borrow().children.len(), 2);
+
+        let logp = x.log_softmax();
+        let node = logp.borrow()[2].clone();
+        assert_eq!(node.borrow().op, Some(Operation::LogSoftmax));
+        assert!(node.borrow().children[0] == x.borrow()[2]);
+    }
+
+    #[test]
+    fn softmax_grads() {
+        let x = Tensor1D::from(&vec![0.3, -0.8, 1.2, 0.5]);
+        let y = Tensor1D::from(&vec![0.1, 0.2, 0.6, 0.1]);
+        let w = Tensor1D::from(&vec![1.5, -0.7, 0.2, 2.]);
+        check!(x, |x: &Tensor1D| x.logsumexp());
+        check!((&x, &w), |(x, w): &(&Tensor1D, &Tensor1D)| w
+            .dot(&x.softmax()));
+        check!((&x, &w), |(x, w): &(&Tensor1D, &Tensor1D)| w
+            .dot(&x.log_softmax()));
+        check!((&x, &y), |(x, y): &(&Tensor1D, &Tensor1D)| y
+            .cross_entropy_with_logits(x));
+
+        let m = Tensor2D::from(&vec![vec![0.3, -0.8, 1.2], vec![0.5, 0.9, -0.1]]);
+        check!(m, |m: &Tensor2D| m.softmax().hadamard(m).sum());
+        check!(m, |m: &Tensor2D| m.log_softmax().pow(2.0).sum());
+        check!(m, |m: &Tensor2D| m.logsumexp().pow(2.0).sum());
+    }
+
+    #[test]
+    fn softmax_create_graph() {
+        // d^2 lse / dx_i^2 = p_i (1 - p_i)
+        let x = Tensor1D::from(&vec![0.3, -0.8, 1.2]);
+        let leaves = x.borrow().clone();
+        let p = values(&x.softmax());
+        let g = grad(&x.logsumexp(), &leaves);
+        let h = grad(&g[0], &leaves);
+        assert_close!(g[0].data(), p[0], 1e-12);
+        assert_close!(h[0].data(), p[0] * (1. - p[0]), 1e-12);
+        assert_close!(h[1].data(), -p[0] * p[1], 1e-12);
+
+        let s = x.softmax();
+        let g = grad(&s.borrow()[1], &leaves);
+        assert_close!(g[1].data(), p[1] * (1. - p[1]), 1e-12);
+        let s = x.log_softmax();
+        let g = grad(&s.borrow()[1], &leaves);
+        assert_close!(g[2].data(), -p[2], 1e-12);
+    }
+}
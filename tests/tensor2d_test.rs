@@ -0,0 +1,199 @@
+#[macro_use]
+mod common;
+use common::{grads, values};
+use rust_minigrad::{grad, Operation, Tensor1D, Tensor2D, Variable};
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn elementwise_values() {
+        let a = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        let b = Tensor2D::from(&vec![vec![5., 6.], vec![7., 8.]]);
+        assert_eq!(values(&(&a + &b)), vec![vec![6., 8.], vec![10., 12.]]);
+        assert_eq!(values(&(&b - &a)), vec![vec![4., 4.], vec![4., 4.]]);
+        assert_eq!(values(&a.hadamard(&b)), vec![vec![5., 12.], vec![21., 32.]]);
+        assert_eq!(values(&(&b / &a)), vec![vec![5., 3.], vec![7. / 3., 2.]]);
+        assert_eq!(values(&a.pow(2.0)), vec![vec![1., 4.], vec![9., 16.]]);
+        assert_eq!(values(&(-&a).relu()), vec![vec![0., 0.], vec![0., 0.]]);
+        assert_eq!(values(&a.exp().ln())[1][1], 4.0_f64.exp().ln());
+        assert_eq!(values(&a.sin())[0][1], 2.0_f64.sin());
+        assert_eq!(values(&a.cos())[1][0], 3.0_f64.cos());
+        assert_eq!(values(&a.apply_fn(|x| x * 2.0))[1], vec![6., 8.]);
+    }
+
+    #[test]
+    fn elementwise_grads() {
+        let a = Tensor2D::from(&vec![vec![0.3, -0.8, 1.2], vec![0.5, 0.9, -0.1]]);
+        let b = Tensor2D::from(&vec![vec![0.2, 0.1, -0.6], vec![0.4, 1.1, -0.7]]);
+        check!((&a, &b), |(a, b): &(&Tensor2D, &Tensor2D)| (*a + *b).sum());
+        check!((&a, &b), |(a, b): &(&Tensor2D, &Tensor2D)| (*a - *b).sum());
+        check!((&a, &b), |(a, b): &(&Tensor2D, &Tensor2D)| a
+            .hadamard(b)
+            .sum());
+        check!((&a, &b), |(a, b): &(&Tensor2D, &Tensor2D)| (*a / *b).sum());
+        check!(a, |a: &Tensor2D| a.sin().sum());
+        check!(a, |a: &Tensor2D| a.cos().sum());
+        check!(a, |a: &Tensor2D| a.exp().sum());
+        check!(a, |a: &Tensor2D| a.pow(2.0).ln().sum());
+        check!(a, |a: &Tensor2D| a.relu().sum());
+        check!(a, |a: &Tensor2D| a.silu().sum());
+        check!(a, |a: &Tensor2D| a.pow(3.0).sum());
+        check!(a, |a: &Tensor2D| a.hadamard(a).mean());
+        check!(a, |a: &Tensor2D| a.apply_fn(|x: &Variable| x.tan()).sum());
+    }
+
+    #[test]
+    fn views() {
+        let m = Tensor2D::from(&vec![vec![1., 2., 3.], vec![4., 5., 6.]]);
+        assert_eq!(m.t().shape(), (3, 2));
+        assert_eq!(
+            values(&m.t()),
+            vec![vec![1., 4.], vec![2., 5.], vec![3., 6.]]
+        );
+        assert_eq!(m.row(1).shape(), (3,));
+        assert_eq!(m.row(1).borrow()[2].data(), 6.);
+        assert_eq!(m.col(2).shape(), (2,));
+        assert_eq!(m.col(2).borrow()[0].data(), 3.);
+        assert_eq!(
+            values(&m.slice(0..2, 1..3)),
+            vec![vec![2., 3.], vec![5., 6.]]
+        );
+        assert_eq!(
+            values(&m.reshape(3, 2)),
+            vec![vec![1., 2.], vec![3., 4.], vec![5., 6.]]
+        );
+        assert_eq!(values(&m.reshape(1, 6))[0], vec![1., 2., 3., 4., 5., 6.]);
+    }
+
+    #[test]
+    fn views_share_variables() {
+        let m = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        assert!(m.t().borrow()[0][1] == m.borrow()[1][0]);
+        assert!(m.row(0).borrow()[1] == m.borrow()[0][1]);
+        assert!(m.col(0).borrow()[1] == m.borrow()[1][0]);
+
+        // the view is not a copy: changes of the data are seen by both
+        m.slice(1..2, 0..2).borrow()[0][1].borrow_mut().data = 10.;
+        assert_eq!(m.borrow()[1][1].data(), 10.);
+    }
+
+    #[test]
+    fn grads_through_views() {
+        let m = Tensor2D::from(&vec![vec![1., 2.], vec![3., 4.]]);
+        let x = Tensor1D::from(&vec![1., -1.]);
+        // row 0 + col 1 + x = (1 + 2 + 1, 2 + 4 - 1)
+        let mut y = (&m.row(0) + &m.col(1) + &x).l2_2();
+        y.backward();
+        let g = |i: usize, j: usize| m.borrow()[i][j].grad();
+        assert_eq!(y.data(), 16. + 25.);
+        assert_eq!(
+            (g(0, 0), g(0, 1), g(1, 0), g(1, 1)),
+            (8., 8. + 10., 0., 10.)
+        );
+
+        let a = Tensor2D::from(&vec![vec![0.3, -0.8, 1.2], vec![0.5, 0.9, -0.1]]);
+        check!(a, |a: &Tensor2D| (a * &a.t()).sum());
+        check!(a, |a: &Tensor2D| (&a.reshape(3, 2) * a).sin().sum());
+        check!(a, |a: &Tensor2D| a.slice(0..2, 1..3).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.t().row(1).dot(&a.col(2)));
+    }
+
+    #[test]
+    fn axis_reductions() {
+        let m = Tensor2D::from(&vec![vec![1., 5., 3.], vec![4., 2., 9.]]);
+        let v = |x: Tensor1D| values(&x);
+        assert_eq!(v(m.sum_axis(0)), vec![5., 7., 12.]);
+        assert_eq!(v(m.sum_axis(1)), vec![9., 15.]);
+        assert_eq!(v(m.mean_axis(0)), vec![2.5, 3.5, 6.]);
+        assert_eq!(v(m.mean_axis(1)), vec![3., 5.]);
+        assert_eq!(v(m.max_axis(0)), vec![4., 5., 9.]);
+        assert_eq!(v(m.max_axis(1)), vec![5., 9.]);
+        assert_eq!(v(m.min_axis(0)), vec![1., 2., 3.]);
+        assert_eq!(v(m.min_axis(1)), vec![1., 2.]);
+        assert_eq!(v(m.var_axis(0)), vec![2.25, 2.25, 9.]);
+        assert_eq!(v(m.var_axis(1)), vec![8. / 3., 26. / 3.]);
+        assert_eq!(v(m.std_axis(0)), vec![1.5, 1.5, 3.]);
+    }
+
+    #[test]
+    fn reductions_are_single_nodes() {
+        let m = Tensor2D::from(&vec![vec![1., 5., 3.], vec![4., 2., 9.]]);
+        let check = |x: Tensor1D, op: Operation, fan_in: usize| {
+            x.borrow().iter().for_each(|it| {
+                assert_eq!(it.borrow().op, Some(op.clone()));
+                assert_eq!(it.borrow().children.len(), fan_in);
+                assert!(it.borrow().children[0].borrow().children.is_empty());
+            });
+        };
+        check(m.sum_axis(0), Operation::Sum, 2);
+        check(m.mean_axis(1), Operation::Mean, 3);
+        check(m.max_axis(0), Operation::Max, 2);
+        check(m.min_axis(1), Operation::Min, 3);
+        check(m.var_axis(0), Operation::Var, 2);
+        check(m.std_axis(1), Operation::Std, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "no axis 2")]
+    fn wrong_axis() {
+        Tensor2D::new(2, 2).sum_axis(2);
+    }
+
+    #[test]
+    fn reduction_grads() {
+        let a = Tensor2D::from(&vec![vec![0.3, -0.8, 1.2], vec![0.5, 0.9, -0.1]]);
+        let w = Tensor1D::from(&vec![0.7, -1.1, 0.4]);
+        check!(a, |a: &Tensor2D| a.sum_axis(0).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.sum_axis(1).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.mean_axis(0).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.mean_axis(1).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.max_axis(0).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.max_axis(1).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.min_axis(0).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.min_axis(1).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.var_axis(0).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.var_axis(1).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.std_axis(0).pow(2.0).sum());
+        check!(a, |a: &Tensor2D| a.std_axis(1).sum());
+        // batch norm
+        check!((&a, &w), |(a, w): &(&Tensor2D, &Tensor1D)| {
+            let norm = (*a - &a.mean_axis(0)) / &a.std_axis(0);
+            (&norm * *w).pow(2.0).sum()
+        });
+    }
+
+    #[test]
+    fn reductions_create_graph() {
+        // d^2 var / dx_i^2 = 2 (n - 1) / n^2, d^2 var / dx_i dx_j = -2 / n^2
+        let x = Tensor1D::from(&vec![1., 3.]);
+        let leaves = x.borrow().clone();
+        let g = grad(&x.var(), &leaves);
+        let h = grad(&g[0], &leaves);
+        assert!((h[0].data() - 0.5).abs() < 1e-12);
+        assert!((h[1].data() + 0.5).abs() < 1e-12);
+
+        let g = grad(&x.mean(), &leaves);
+        assert_eq!(g[1].data(), 0.5);
+        let g = grad(&x.max(), &leaves);
+        assert_eq!((g[0].data(), g[1].data()), (0., 1.));
+        let g = grad(&x.std(), &leaves);
+        assert!((g[0].data() + 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn std_of_constant_lane() {
+        let x = Tensor1D::from(&vec![1., 1.]);
+        x.std().backward();
+        assert_eq!(grads(&x), vec![0., 0.]);
+        let leaves = x.borrow().clone();
+        let g = grad(&x.std(), &leaves);
+        assert_eq!((g[0].data(), g[1].data()), (0., 0.));
+
+        // only the constant column gets zero grads
+        let m = Tensor2D::from(&vec![vec![2., 1.], vec![2., 3.]]);
+        m.std_axis(0).sum().backward();
+        assert_eq!(grads(&m), vec![vec![0., -0.5], vec![0., 0.5]]);
+    }
+}
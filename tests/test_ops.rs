@@ -1,9 +1,6 @@
-use rand::random;
-use rust_minigrad::{Operation, Tensor1D, Tensor2D, Variable, VariableData};
-
 #[cfg(test)]
 mod test {
-    use rust_minigrad::{Tensor1D, Variable};
+    use rust_minigrad::Variable;
 
     #[test]
     fn test1() {